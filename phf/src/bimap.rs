@@ -0,0 +1,172 @@
+//! An immutable bidirectional map constructed at compile time.
+use core::prelude::*;
+use core::fmt;
+use core::slice;
+use shared;
+use shared::{PhfHash, PhfHasher, PhfBorrow, XxHasher};
+
+/// An immutable bidirectional map constructed at compile time.
+///
+/// Each entry of a `BiMap` pairs a left value with a right value, and entries
+/// can be looked up from either side. Neither side may contain duplicates.
+///
+/// `BiMap`s may be created with the `phf_bimap` macro:
+///
+/// ```rust
+/// # #![feature(phase)]
+/// extern crate phf;
+/// #[phase(plugin)]
+/// extern crate phf_mac;
+///
+/// static STATUSES: phf::BiMap<&'static str, u16> = phf_bimap! {
+///    "OK" => 200u16,
+///    "Not Found" => 404u16,
+/// };
+///
+/// # fn main() {
+/// assert_eq!(Some(&404), STATUSES.get_by_left("Not Found"));
+/// assert_eq!(Some(&"OK"), STATUSES.get_by_right(&200u16));
+/// # }
+/// ```
+///
+/// ## Note
+///
+/// The fields of this struct are public so that they may be initialized by the
+/// `phf_bimap` macro. They are subject to change at any time and should never
+/// be accessed directly.
+pub struct BiMap<L:'static, R:'static, H:'static = XxHasher> {
+    #[doc(hidden)]
+    pub hasher: H,
+    #[doc(hidden)]
+    pub left_key: u64,
+    #[doc(hidden)]
+    pub left_disps: &'static [(u32, u32)],
+    #[doc(hidden)]
+    pub left_idxs: &'static [uint],
+    #[doc(hidden)]
+    pub right_key: u64,
+    #[doc(hidden)]
+    pub right_disps: &'static [(u32, u32)],
+    #[doc(hidden)]
+    pub right_idxs: &'static [uint],
+    #[doc(hidden)]
+    pub entries: &'static [(L, R)],
+}
+
+impl<L, R, H> fmt::Show for BiMap<L, R, H> where L: fmt::Show, R: fmt::Show, H: PhfHasher {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "{{"));
+        let mut first = true;
+        for &(ref l, ref r) in self.entries() {
+            if !first {
+                try!(write!(fmt, ", "));
+            }
+            try!(write!(fmt, "{} <-> {}", l, r))
+            first = false;
+        }
+        write!(fmt, "}}")
+    }
+}
+
+impl<L, R, H> BiMap<L, R, H> where L: PhfHash+Eq, H: PhfHasher {
+    /// Returns a reference to the right value paired with `left`.
+    ///
+    /// `left` may be any borrowed form of the left type.
+    pub fn get_by_left<Sized? T>(&self, left: &T) -> Option<&R>
+            where T: PhfHash+Eq, L: PhfBorrow<T> {
+        let idx = match self.find(left, self.left_key, self.left_disps, self.left_idxs) {
+            Some(idx) => idx,
+            None => return None,
+        };
+        let entry = &self.entries[idx];
+        if left == entry.0.borrow() {
+            Some(&entry.1)
+        } else {
+            None
+        }
+    }
+
+    /// Determines if `left` is on the left side of the `BiMap`.
+    pub fn contains_left<Sized? T>(&self, left: &T) -> bool
+            where T: PhfHash+Eq, L: PhfBorrow<T> {
+        self.get_by_left(left).is_some()
+    }
+}
+
+impl<L, R, H> BiMap<L, R, H> where R: PhfHash+Eq, H: PhfHasher {
+    /// Returns a reference to the left value paired with `right`.
+    ///
+    /// `right` may be any borrowed form of the right type.
+    pub fn get_by_right<Sized? T>(&self, right: &T) -> Option<&L>
+            where T: PhfHash+Eq, R: PhfBorrow<T> {
+        let idx = match self.find(right, self.right_key, self.right_disps, self.right_idxs) {
+            Some(idx) => idx,
+            None => return None,
+        };
+        let entry = &self.entries[idx];
+        if right == entry.1.borrow() {
+            Some(&entry.0)
+        } else {
+            None
+        }
+    }
+
+    /// Determines if `right` is on the right side of the `BiMap`.
+    pub fn contains_right<Sized? T>(&self, right: &T) -> bool
+            where T: PhfHash+Eq, R: PhfBorrow<T> {
+        self.get_by_right(right).is_some()
+    }
+}
+
+impl<L, R, H> BiMap<L, R, H> where H: PhfHasher {
+    /// Returns true if the `BiMap` is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries in the `BiMap`.
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+
+    fn find<Sized? T>(&self, key: &T, seed: u64, disps: &[(u32, u32)], idxs: &[uint])
+                      -> Option<uint> where T: PhfHash {
+        if self.is_empty() {
+            return None;
+        }
+
+        let (g, f1, f2) = shared::phf_hash(&self.hasher, key, seed);
+        let (d1, d2) = disps[(g % (disps.len() as u32)) as uint];
+        Some(idxs[(shared::displace(f1, f2, d1, d2) % (idxs.len() as u32)) as uint])
+    }
+
+    /// Returns an iterator over the left/right pairs in the map.
+    ///
+    /// Entries are returned in the same order in which they were defined.
+    pub fn entries<'a>(&'a self) -> Entries<'a, L, R> {
+        Entries { iter: self.entries.iter() }
+    }
+}
+
+/// An iterator over the left/right pairs in a `BiMap`.
+pub struct Entries<'a, L:'a, R:'a> {
+    iter: slice::Items<'a, (L, R)>,
+}
+
+impl<'a, L, R> Iterator<&'a (L, R)> for Entries<'a, L, R> {
+    fn next(&mut self) -> Option<&'a (L, R)> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, L, R> DoubleEndedIterator<&'a (L, R)> for Entries<'a, L, R> {
+    fn next_back(&mut self) -> Option<&'a (L, R)> {
+        self.iter.next_back()
+    }
+}
+
+impl<'a, L, R> ExactSize<&'a (L, R)> for Entries<'a, L, R> {}
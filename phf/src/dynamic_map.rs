@@ -0,0 +1,199 @@
+//! An immutable map constructed at runtime.
+use core::prelude::*;
+use core::fmt;
+use core::hash::Writer;
+use collections::vec::Vec;
+use collections::slice::SliceAllocPrelude;
+pub use map::{Entries, Keys, Values};
+use shared;
+use shared::{PhfHash, PhfHasher, PhfBorrow, XxHasher};
+
+/// An immutable map constructed at runtime.
+///
+/// A `DynamicMap` uses the same perfect hashing scheme as a `Map`, but owns
+/// its entries and generates its hash when it is created rather than at
+/// compile time. This is useful when the keys aren't known until the program
+/// starts but won't change afterwards.
+///
+/// ```rust
+/// use phf::DynamicMap;
+///
+/// let map = DynamicMap::from_entries(vec![("hello", 10i), ("world", 11)]).unwrap();
+/// assert_eq!(Some(&10), map.get(&"hello"));
+/// ```
+pub struct DynamicMap<K, V, H = XxHasher> {
+    hasher: H,
+    key: u64,
+    disps: Vec<(u32, u32)>,
+    entries: Vec<(K, V)>,
+}
+
+impl<K, V, H> fmt::Show for DynamicMap<K, V, H>
+        where K: fmt::Show, V: fmt::Show, H: PhfHasher {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "{{"));
+        let mut first = true;
+        for &(ref k, ref v) in self.entries() {
+            if !first {
+                try!(write!(fmt, ", "));
+            }
+            try!(write!(fmt, "{}: {}", k, v))
+            first = false;
+        }
+        write!(fmt, "}}")
+    }
+}
+
+impl<K, V, H> Index<K, V> for DynamicMap<K, V, H> where K: PhfHash+Eq, H: PhfHasher {
+    fn index(&self, k: &K) -> &V {
+        self.get(k).expect("invalid key")
+    }
+}
+
+// Borrows a key so the hash can be generated without moving the entries.
+struct KeyRef<'a, K:'a>(&'a K);
+
+impl<'a, K> PhfHash for KeyRef<'a, K> where K: PhfHash {
+    #[inline]
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        self.0.phf_hash_into(state)
+    }
+}
+
+fn has_duplicates<K>(keys: &[KeyRef<K>]) -> bool where K: PhfHash+Eq {
+    // Equal keys always feed identical input to the hasher, so only those keys
+    // need to be compared directly.
+    for group in shared::find_collisions(keys).iter() {
+        for (i, &a) in group.iter().enumerate() {
+            for &b in group.slice_from(i + 1).iter() {
+                if keys[a].0 == keys[b].0 {
+                    return true;
+                }
+            }
+        }
+    }
+
+    false
+}
+
+impl<K, V> DynamicMap<K, V> where K: PhfHash+Eq {
+    /// Constructs a `DynamicMap` from a list of key/value pairs.
+    ///
+    /// Returns `None` if `entries` contains duplicate keys, or if a perfect
+    /// hash could not be found for them.
+    pub fn from_entries(entries: Vec<(K, V)>) -> Option<DynamicMap<K, V>> {
+        DynamicMap::from_entries_with_hasher(entries, XxHasher)
+    }
+}
+
+impl<K, V, H> DynamicMap<K, V, H> where K: PhfHash+Eq, H: PhfHasher {
+    /// Like `from_entries`, but uses the provided hasher.
+    pub fn from_entries_with_hasher(entries: Vec<(K, V)>, hasher: H)
+                                    -> Option<DynamicMap<K, V, H>> {
+        let state = {
+            let keys = entries.iter().map(|e| KeyRef(&e.0)).collect::<Vec<_>>();
+            if has_duplicates(keys[]) {
+                return None;
+            }
+            match shared::generate_hash_keys(keys[], &hasher, &shared::DEFAULT_PARAMS) {
+                Some(state) => state,
+                None => return None,
+            }
+        };
+
+        let mut slots = entries.into_iter().map(|e| Some(e)).collect::<Vec<_>>();
+        let entries = state.map.iter().map(|&idx| slots[idx].take().unwrap()).collect();
+
+        Some(DynamicMap {
+            hasher: hasher,
+            key: state.key,
+            disps: state.disps,
+            entries: entries,
+        })
+    }
+
+    /// Returns a reference to the value that `key` maps to.
+    ///
+    /// `key` may be any borrowed form of the key type, for example a `&str`
+    /// for a `DynamicMap<String, V>`.
+    pub fn get<Sized? T>(&self, key: &T) -> Option<&V> where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.get_entry_(key, |k| key == k.borrow()).map(|e| &e.1)
+    }
+
+    /// Determines if `key` is in the `DynamicMap`.
+    pub fn contains_key<Sized? T>(&self, key: &T) -> bool where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.get(key).is_some()
+    }
+
+    /// Returns a reference to the map's internal instance of the given key.
+    ///
+    /// This can be useful for interning schemes.
+    pub fn get_key<Sized? T>(&self, key: &T) -> Option<&K> where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.get_entry_(key, |k| key == k.borrow()).map(|e| &e.0)
+    }
+}
+
+impl<K, V, H> DynamicMap<K, V, H> where H: PhfHasher {
+    /// Returns true if the `DynamicMap` is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries in the `DynamicMap`.
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+
+    fn get_entry_<Sized? T>(&self, key: &T, check: |&K| -> bool) -> Option<&(K, V)> where T: PhfHash {
+        if self.is_empty() {
+            return None;
+        }
+
+        let (g, f1, f2) = shared::phf_hash(&self.hasher, key, self.key);
+        let (d1, d2) = self.disps[(g % (self.disps.len() as u32)) as uint];
+        let entry = &self.entries[(shared::displace(f1, f2, d1, d2) % (self.entries.len() as u32))
+                                  as uint];
+        if check(&entry.0) {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    /// Like `get`, but returns both the key and the value.
+    pub fn get_entry<Sized? T>(&self, key: &T) -> Option<(&K, &V)> where T: PhfHash+Equiv<K> {
+        self.get_entry_(key, |k| key.equiv(k)).map(|e| (&e.0, &e.1))
+    }
+
+    /// Like `get`, but can operate on any type that is equivalent to a key.
+    pub fn get_equiv<Sized? T>(&self, key: &T) -> Option<&V> where T: PhfHash+Equiv<K> {
+        self.get_entry_(key, |k| key.equiv(k)).map(|e| &e.1)
+    }
+
+    /// Like `get_key`, but can operate on any type that is equivalent to a
+    /// key.
+    pub fn get_key_equiv<Sized? T>(&self, key: &T) -> Option<&K> where T: PhfHash+Equiv<K> {
+        self.get_entry_(key, |k| key.equiv(k)).map(|e| &e.0)
+    }
+
+    /// Returns an iterator over the key/value pairs in the map.
+    ///
+    /// Entries are returned in an arbitrary but fixed order.
+    pub fn entries<'a>(&'a self) -> Entries<'a, K, V> {
+        Entries { iter: self.entries.iter() }
+    }
+
+    /// Returns an iterator over the keys in the map.
+    ///
+    /// Keys are returned in an arbitrary but fixed order.
+    pub fn keys<'a>(&'a self) -> Keys<'a, K, V> {
+        Keys { iter: self.entries().map(|e| &e.0) }
+    }
+
+    /// Returns an iterator over the values in the map.
+    ///
+    /// Values are returned in an arbitrary but fixed order.
+    pub fn values<'a>(&'a self) -> Values<'a, K, V> {
+        Values { iter: self.entries().map(|e| &e.1) }
+    }
+}
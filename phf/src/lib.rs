@@ -1,24 +1,64 @@
 //! Compile time optimized maps and sets.
 //!
 //! Keys can be string literals, byte string literals, byte literals, char
-//! literals, or any of the fixed-size integral types.
+//! literals, or any of the integral types, as well as tuples and fixed-size
+//! arrays of those. String keys wrapped in `NoCase` are matched ignoring
+//! ASCII case, and those wrapped in `FoldCase` are matched ignoring Unicode
+//! case. Variants of fieldless enums marked with `#[phf_enum]` can also be
+//! used as keys.
+//!
+//! A `BiMap` pairs two sets of keys, and can be queried from either side. A
+//! `MultiMap` allows a key to map to several values, and a `PrefixMap`
+//! finds the entries whose keys are prefixes of a string. A `SortedMap` keeps
+//! its entries sorted by key to support range queries, and a `RangeMap` maps
+//! non-overlapping integer ranges to values.
+//!
+//! A `DynamicMap` offers the same lookup scheme for entries which are only
+//! known at runtime, and a `MappedMap` reads a serialized table directly
+//! from a buffer, such as a memory mapped file.
+//!
+//! All of the data structures are generic over the `PhfHasher` used to hash
+//! keys, defaulting to `XxHasher`. `Sip13Hasher`, `FxHasher` and
+//! `MulShiftHasher` are also provided.
+//!
+//! When the `serde` feature is enabled, `Map`, `Set`, `OrderedMap` and
+//! `OrderedSet` implement serde's `Serialize`, as maps and sequences
+//! respectively.
 #![doc(html_root_url="https://sfackler.github.io/doc")]
 #![warn(missing_docs)]
-#![feature(macro_rules, tuple_indexing, phase, globs)]
+#![feature(macro_rules, tuple_indexing, phase, globs, slicing_syntax)]
 #![no_std]
 
 #[phase(plugin, link)]
 extern crate core;
+extern crate collections;
+#[cfg(feature = "serde")]
+extern crate serde;
 
-pub use shared::PhfHash;
+pub use shared::{PhfHash, PhfHasher, PhfBorrow, NoCase, FoldCase};
+pub use shared::{XxHasher, Sip13Hasher, FxHasher, MulShiftHasher};
 #[doc(inline)]
 pub use map::Map;
 #[doc(inline)]
-pub use set::Set;
+pub use set::{Set, SetLike, SetValues};
 #[doc(inline)]
 pub use ordered_map::OrderedMap;
 #[doc(inline)]
 pub use ordered_set::OrderedSet;
+#[doc(inline)]
+pub use bimap::BiMap;
+#[doc(inline)]
+pub use multimap::MultiMap;
+#[doc(inline)]
+pub use prefix_map::PrefixMap;
+#[doc(inline)]
+pub use sorted_map::SortedMap;
+#[doc(inline)]
+pub use range_map::RangeMap;
+#[doc(inline)]
+pub use dynamic_map::DynamicMap;
+#[doc(inline)]
+pub use mapped_map::MappedMap;
 
 #[path="../../shared/mod.rs"]
 mod shared;
@@ -26,6 +66,15 @@ pub mod map;
 pub mod set;
 pub mod ordered_map;
 pub mod ordered_set;
+pub mod bimap;
+pub mod multimap;
+pub mod prefix_map;
+pub mod sorted_map;
+pub mod range_map;
+pub mod dynamic_map;
+pub mod mapped_map;
+#[cfg(feature = "serde")]
+mod serde_impls;
 
 mod std {
     pub use core::fmt;
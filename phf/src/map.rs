@@ -3,8 +3,10 @@ use core::prelude::*;
 use core::iter;
 use core::slice;
 use core::fmt;
+use core::hash::{Hash, Writer};
+use core::hash::sip;
 use shared;
-use shared::PhfHash;
+use shared::{PhfHash, PhfHasher, PhfBorrow, XxHasher};
 
 /// An immutable map constructed at compile time.
 ///
@@ -24,21 +26,43 @@ use shared::PhfHash;
 /// # fn main() {}
 /// ```
 ///
+/// The hash function used can be selected with the `hasher` option of the
+/// `phf_map` macro, and must match the `H` parameter of the `Map`:
+///
+/// ```rust
+/// # #![feature(phase)]
+/// extern crate phf;
+/// #[phase(plugin)]
+/// extern crate phf_mac;
+///
+/// static MY_MAP: phf::Map<u32, int, phf::FxHasher> = phf_map! {
+///    #[hasher = "fx"]
+///    1u32 => 10,
+///    2u32 => 11,
+/// };
+///
+/// # fn main() {}
+/// ```
+///
 /// ## Note
 ///
 /// The fields of this struct are public so that they may be initialized by the
 /// `phf_map` macro. They are subject to change at any time and should never
 /// be accessed directly.
-pub struct Map<K:'static, V:'static> {
+pub struct Map<K:'static, V:'static, H:'static = XxHasher> {
+    #[doc(hidden)]
+    pub hasher: H,
     #[doc(hidden)]
     pub key: u64,
     #[doc(hidden)]
     pub disps: &'static [(u32, u32)],
     #[doc(hidden)]
+    pub idxs: &'static [uint],
+    #[doc(hidden)]
     pub entries: &'static [(K, V)],
 }
 
-impl<K, V> fmt::Show for Map<K, V> where K: fmt::Show, V: fmt::Show {
+impl<K, V, H> fmt::Show for Map<K, V, H> where K: fmt::Show, V: fmt::Show, H: PhfHasher {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         try!(write!(fmt, "{{"));
         let mut first = true;
@@ -53,20 +77,44 @@ impl<K, V> fmt::Show for Map<K, V> where K: fmt::Show, V: fmt::Show {
     }
 }
 
-impl<K, V> Index<K, V> for Map<K, V> where K: PhfHash+Eq {
+impl<K, V, H> PartialEq for Map<K, V, H> where K: PhfHash+Eq, V: PartialEq, H: PhfHasher {
+    /// Two `Map`s are equal if they contain the same entries, regardless of
+    /// the order in which they were defined.
+    fn eq(&self, other: &Map<K, V, H>) -> bool {
+        self.len() == other.len()
+            && self.entries().all(|&(ref k, ref v)| other.get(k).map_or(false, |o| v == o))
+    }
+}
+
+impl<K, V, H> Eq for Map<K, V, H> where K: PhfHash+Eq, V: Eq, H: PhfHasher {}
+
+impl<K, V, H, S> Hash<S> for Map<K, V, H> where K: Hash, V: Hash, H: PhfHasher, S: Writer {
+    fn hash(&self, state: &mut S) {
+        // The order of the entries depends on the hash key, so the entry hashes
+        // are combined in a way that doesn't depend on it to match `eq`.
+        let sum = self.entries().fold(0u64, |sum, e| sum + sip::hash(e));
+        self.len().hash(state);
+        sum.hash(state);
+    }
+}
+
+impl<K, V, H> Index<K, V> for Map<K, V, H> where K: PhfHash+Eq, H: PhfHasher {
     fn index(&self, k: &K) -> &V {
         self.get(k).expect("invalid key")
     }
 }
 
-impl<K, V> Map<K, V> where K: PhfHash+Eq {
+impl<K, V, H> Map<K, V, H> where K: PhfHash+Eq, H: PhfHasher {
     /// Returns a reference to the value that `key` maps to.
-    pub fn get(&self, key: &K) -> Option<&V> {
-        self.get_entry_(key, |k| key == k).map(|e| &e.1)
+    ///
+    /// `key` may be any borrowed form of the key type, for example a `&str`
+    /// for a `Map<&'static str, V>`.
+    pub fn get<Sized? T>(&self, key: &T) -> Option<&V> where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.get_entry_(key, |k| key == k.borrow()).map(|e| &e.1)
     }
 
     /// Determines if `key` is in the `Map`.
-    pub fn contains_key(&self, key: &K) -> bool {
+    pub fn contains_key<Sized? T>(&self, key: &T) -> bool where T: PhfHash+Eq, K: PhfBorrow<T> {
         self.get(key).is_some()
     }
 
@@ -74,12 +122,12 @@ impl<K, V> Map<K, V> where K: PhfHash+Eq {
     /// key.
     ///
     /// This can be useful for interning schemes.
-    pub fn get_key(&self, key: &K) -> Option<&K> {
-        self.get_entry_(key, |k| key == k).map(|e| &e.0)
+    pub fn get_key<Sized? T>(&self, key: &T) -> Option<&K> where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.get_entry_(key, |k| key == k.borrow()).map(|e| &e.0)
     }
 }
 
-impl<K, V> Map<K, V> {
+impl<K, V, H> Map<K, V, H> where H: PhfHasher {
     /// Returns true if the `Map` is empty.
     pub fn is_empty(&self) -> bool {
         self.len() == 0
@@ -91,10 +139,15 @@ impl<K, V> Map<K, V> {
     }
 
     fn get_entry_<Sized? T>(&self, key: &T, check: |&K| -> bool) -> Option<&(K, V)> where T: PhfHash {
-        let (g, f1, f2) = key.phf_hash(self.key);
+        if self.disps.is_empty() {
+            return None;
+        }
+
+        let (g, f1, f2) = shared::phf_hash(&self.hasher, key, self.key);
         let (d1, d2) = self.disps[(g % (self.disps.len() as u32)) as uint];
-        let entry = &self.entries[(shared::displace(f1, f2, d1, d2) % (self.entries.len() as u32))
-                                  as uint];
+        let idx = self.idxs[(shared::displace(f1, f2, d1, d2) % (self.idxs.len() as u32)) as uint];
+        let entry = &self.entries[idx];
+
         if check(&entry.0) {
             Some(entry)
         } else {
@@ -126,7 +179,7 @@ impl<K, V> Map<K, V> {
 
     /// Returns an iterator over the key/value pairs in the map.
     ///
-    /// Entries are retuned in an arbitrary but fixed order.
+    /// Entries are returned in an arbitrary but fixed order.
     pub fn entries<'a>(&'a self) -> Entries<'a, K, V> {
         Entries { iter: self.entries.iter() }
     }
@@ -146,9 +199,11 @@ impl<K, V> Map<K, V> {
     }
 }
 
-/// An iterator over the key/value pairs in a `Map`.
+/// An iterator over the key/value pairs in a `Map` or `DynamicMap`.
 pub struct Entries<'a, K:'a, V:'a> {
-    iter: slice::Items<'a, (K, V)>,
+    // Public so that `DynamicMap` can iterate over its own entries.
+    #[doc(hidden)]
+    pub iter: slice::Items<'a, (K, V)>,
 }
 
 impl<'a, K, V> Iterator<&'a (K, V)> for Entries<'a, K, V> {
@@ -169,9 +224,10 @@ impl<'a, K, V> DoubleEndedIterator<&'a (K, V)> for Entries<'a, K, V> {
 
 impl<'a, K, V> ExactSize<&'a (K, V)> for Entries<'a, K, V> {}
 
-/// An iterator over the keys in a `Map`.
+/// An iterator over the keys in a `Map` or `DynamicMap`.
 pub struct Keys<'a, K:'a, V:'a> {
-    iter: iter::Map<'a, &'a (K, V), &'a K, Entries<'a, K, V>>,
+    #[doc(hidden)]
+    pub iter: iter::Map<'a, &'a (K, V), &'a K, Entries<'a, K, V>>,
 }
 
 impl<'a, K, V> Iterator<&'a K> for Keys<'a, K, V> {
@@ -192,9 +248,10 @@ impl<'a, K, V> DoubleEndedIterator<&'a K> for Keys<'a, K, V> {
 
 impl<'a, K, V> ExactSize<&'a K> for Keys<'a, K, V> {}
 
-/// An iterator over the values in a `Map`.
+/// An iterator over the values in a `Map` or `DynamicMap`.
 pub struct Values<'a, K:'a, V:'a> {
-    iter: iter::Map<'a, &'a (K, V), &'a V, Entries<'a, K, V>>,
+    #[doc(hidden)]
+    pub iter: iter::Map<'a, &'a (K, V), &'a V, Entries<'a, K, V>>,
 }
 
 impl<'a, K, V> Iterator<&'a V> for Values<'a, K, V> {
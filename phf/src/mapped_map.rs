@@ -0,0 +1,346 @@
+//! An immutable map read directly from a serialized buffer.
+//!
+//! The serialized form starts with a header, followed by the displacement
+//! table, the entry table and the blob holding the key and value bytes. All
+//! integers are little endian.
+//!
+//! | Field    | Size              | Contents                                  |
+//! |----------|-------------------|-------------------------------------------|
+//! | magic    | 4                 | `PHFM`                                    |
+//! | version  | 4                 | `1`                                       |
+//! | key      | 8                 | the hash key                              |
+//! | ndisps   | 4                 | the number of displacements               |
+//! | len      | 4                 | the number of entries                     |
+//! | blob_len | 4                 | the length of the blob                    |
+//! | disps    | 8 * ndisps        | `(d1, d2)` pairs of `u32`s                |
+//! | entries  | 16 * len          | key offset, key length, value offset and  |
+//! |          |                   | value length, as `u32`s, in slot order    |
+//! | blob     | blob_len          | key and value bytes                       |
+//!
+//! Offsets are relative to the start of the blob. Keys are hashed with
+//! `XxHasher`.
+use core::prelude::*;
+use core::fmt;
+use collections::vec::Vec;
+use shared;
+use shared::XxHasher;
+
+const MAGIC: &'static [u8] = b"PHFM";
+const VERSION: u32 = 1;
+const HEADER_LEN: uint = 28;
+
+/// An error encountered when loading a `MappedMap`.
+#[deriving(PartialEq, Eq, Clone)]
+pub enum MappedMapError {
+    /// The buffer doesn't start with the expected magic bytes.
+    InvalidMagic,
+    /// The buffer uses an unsupported version of the format.
+    UnsupportedVersion(u32),
+    /// The length of the buffer doesn't match the lengths in its header.
+    InvalidLength,
+    /// The header describes entries but no displacements.
+    InvalidHeader,
+    /// The entry at the given slot lies outside of the blob.
+    InvalidEntry(uint),
+}
+
+impl fmt::Show for MappedMapError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MappedMapError::InvalidMagic => write!(fmt, "invalid magic bytes"),
+            MappedMapError::UnsupportedVersion(v) => write!(fmt, "unsupported version {}", v),
+            MappedMapError::InvalidLength => write!(fmt, "buffer length doesn't match header"),
+            MappedMapError::InvalidHeader => write!(fmt, "entries present without displacements"),
+            MappedMapError::InvalidEntry(i) => write!(fmt, "entry {} is out of bounds", i),
+        }
+    }
+}
+
+/// An error encountered when serializing entries for a `MappedMap`.
+#[deriving(PartialEq, Eq, Clone)]
+pub enum SerializeError {
+    /// The entry at the given index has the same key as an earlier entry.
+    DuplicateKey(uint),
+    /// A perfect hash could not be found for the keys.
+    HashFailed,
+    /// The entries are too large for the format's 32 bit lengths and offsets.
+    TooLarge,
+}
+
+impl fmt::Show for SerializeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SerializeError::DuplicateKey(i) => write!(fmt, "entry {} has a duplicate key", i),
+            SerializeError::HashFailed => write!(fmt, "unable to find a perfect hash"),
+            SerializeError::TooLarge => write!(fmt, "entries are too large to serialize"),
+        }
+    }
+}
+
+fn read_u32(data: &[u8], pos: uint) -> u32 {
+    data[pos] as u32 | (data[pos + 1] as u32 << 8) | (data[pos + 2] as u32 << 16)
+        | (data[pos + 3] as u32 << 24)
+}
+
+fn read_u64(data: &[u8], pos: uint) -> u64 {
+    read_u32(data, pos) as u64 | (read_u32(data, pos + 4) as u64 << 32)
+}
+
+fn push_u32(buf: &mut Vec<u8>, v: u32) {
+    for i in range(0u, 4) {
+        buf.push((v >> (i * 8)) as u8);
+    }
+}
+
+fn push_u64(buf: &mut Vec<u8>, v: u64) {
+    push_u32(buf, v as u32);
+    push_u32(buf, (v >> 32) as u32);
+}
+
+// Returns the index of the first entry whose key repeats an earlier one.
+fn find_duplicate(keys: &[&[u8]]) -> Option<uint> {
+    // Equal keys always feed identical input to the hasher, so only those keys
+    // need to be compared directly.
+    let mut duplicate = None;
+    for group in shared::find_collisions(keys).iter() {
+        for (i, &b) in group.iter().enumerate() {
+            if group.slice_to(i).iter().any(|&a| keys[a] == keys[b]) {
+                if duplicate.map_or(true, |d| b < d) {
+                    duplicate = Some(b);
+                }
+                break;
+            }
+        }
+    }
+    duplicate
+}
+
+/// Serializes key/value pairs into the format read by `MappedMap`.
+///
+/// Returns an error if `entries` contains duplicate keys, if a perfect hash
+/// could not be found for the keys, or if the entries are too large for the
+/// format's 32 bit offsets.
+pub fn serialize(entries: &[(&[u8], &[u8])]) -> Result<Vec<u8>, SerializeError> {
+    let blob_len = entries.iter().fold(0u64, |len, e| len + e.0.len() as u64 + e.1.len() as u64);
+    if blob_len > 0xffffffff || entries.len() as u64 > 0xffffffff {
+        return Err(SerializeError::TooLarge);
+    }
+
+    let keys = entries.iter().map(|e| e.0).collect::<Vec<_>>();
+    match find_duplicate(keys[]) {
+        Some(i) => return Err(SerializeError::DuplicateKey(i)),
+        None => {}
+    }
+    let state = match shared::generate_hash_keys(keys[], &XxHasher, &shared::DEFAULT_PARAMS) {
+        Some(state) => state,
+        None => return Err(SerializeError::HashFailed),
+    };
+
+    let mut buf = Vec::with_capacity(HEADER_LEN + state.disps.len() * 8 + entries.len() * 16
+                                     + blob_len as uint);
+    buf.push_all(MAGIC);
+    push_u32(&mut buf, VERSION);
+    push_u64(&mut buf, state.key);
+    push_u32(&mut buf, state.disps.len() as u32);
+    push_u32(&mut buf, entries.len() as u32);
+    push_u32(&mut buf, blob_len as u32);
+    for &(d1, d2) in state.disps.iter() {
+        push_u32(&mut buf, d1);
+        push_u32(&mut buf, d2);
+    }
+
+    let mut offset = 0u32;
+    for &idx in state.map.iter() {
+        let (key, value) = entries[idx];
+        push_u32(&mut buf, offset);
+        push_u32(&mut buf, key.len() as u32);
+        offset += key.len() as u32;
+        push_u32(&mut buf, offset);
+        push_u32(&mut buf, value.len() as u32);
+        offset += value.len() as u32;
+    }
+    for &idx in state.map.iter() {
+        let (key, value) = entries[idx];
+        buf.push_all(key);
+        buf.push_all(value);
+    }
+
+    Ok(buf)
+}
+
+/// An immutable map of byte strings, read directly from a buffer created by
+/// `serialize`.
+///
+/// Loading a `MappedMap` validates the buffer's header and the bounds of
+/// every entry, after which lookups read from the buffer without copying or
+/// allocating. This makes it suitable for tables stored in memory mapped
+/// files.
+///
+/// ```rust
+/// use phf::mapped_map::{mod, MappedMap};
+///
+/// let data = mapped_map::serialize(&[(b"hello", b"1"), (b"world", b"2")]).unwrap();
+/// let map = MappedMap::new(data[]).unwrap();
+/// assert_eq!(Some(b"1"), map.get(b"hello"));
+/// ```
+#[deriving(Clone)]
+pub struct MappedMap<'a> {
+    key: u64,
+    disps: &'a [u8],
+    entries: &'a [u8],
+    blob: &'a [u8],
+}
+
+impl<'a> fmt::Show for MappedMap<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "{{"));
+        let mut first = true;
+        for (k, v) in self.entries() {
+            if !first {
+                try!(write!(fmt, ", "));
+            }
+            try!(write!(fmt, "{}: {}", k, v))
+            first = false;
+        }
+        write!(fmt, "}}")
+    }
+}
+
+impl<'a> MappedMap<'a> {
+    /// Loads a `MappedMap` from a buffer created by `serialize`.
+    pub fn new(data: &'a [u8]) -> Result<MappedMap<'a>, MappedMapError> {
+        if data.len() < HEADER_LEN {
+            return Err(MappedMapError::InvalidLength);
+        }
+        if data[..4] != MAGIC {
+            return Err(MappedMapError::InvalidMagic);
+        }
+        let version = read_u32(data, 4);
+        if version != VERSION {
+            return Err(MappedMapError::UnsupportedVersion(version));
+        }
+
+        let key = read_u64(data, 8);
+        let ndisps = read_u32(data, 16) as u64;
+        let len = read_u32(data, 20) as u64;
+        let blob_len = read_u32(data, 24) as u64;
+        if len > 0 && ndisps == 0 {
+            return Err(MappedMapError::InvalidHeader);
+        }
+        if data.len() as u64 != HEADER_LEN as u64 + ndisps * 8 + len * 16 + blob_len {
+            return Err(MappedMapError::InvalidLength);
+        }
+
+        let entries_start = HEADER_LEN + ndisps as uint * 8;
+        let blob_start = entries_start + len as uint * 16;
+        let map = MappedMap {
+            key: key,
+            disps: data[HEADER_LEN..entries_start],
+            entries: data[entries_start..blob_start],
+            blob: data[blob_start..],
+        };
+
+        for i in range(0, len as uint) {
+            let pos = i * 16;
+            for &field in [0u, 8].iter() {
+                let offset = read_u32(map.entries, pos + field) as u64;
+                let len = read_u32(map.entries, pos + field + 4) as u64;
+                if offset + len > blob_len {
+                    return Err(MappedMapError::InvalidEntry(i));
+                }
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Returns the number of entries in the `MappedMap`.
+    pub fn len(&self) -> uint {
+        self.entries.len() / 16
+    }
+
+    /// Returns true if the `MappedMap` is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn bytes(&self, pos: uint) -> &'a [u8] {
+        let offset = read_u32(self.entries, pos) as uint;
+        let len = read_u32(self.entries, pos + 4) as uint;
+        self.blob[offset..offset + len]
+    }
+
+    fn entry(&self, slot: uint) -> (&'a [u8], &'a [u8]) {
+        (self.bytes(slot * 16), self.bytes(slot * 16 + 8))
+    }
+
+    /// Returns the value that `key` maps to.
+    pub fn get(&self, key: &[u8]) -> Option<&'a [u8]> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let (g, f1, f2) = shared::phf_hash(&XxHasher, key, self.key);
+        let pos = (g % (self.disps.len() as u32 / 8)) as uint * 8;
+        let (d1, d2) = (read_u32(self.disps, pos), read_u32(self.disps, pos + 4));
+        let slot = (shared::displace(f1, f2, d1, d2) % (self.len() as u32)) as uint;
+        let (k, v) = self.entry(slot);
+        if k == key {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    /// Determines if `key` is in the `MappedMap`.
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns an iterator over the key/value pairs in the map.
+    ///
+    /// Entries are returned in an arbitrary but fixed order.
+    pub fn entries(&self) -> Entries<'a> {
+        Entries {
+            map: self.clone(),
+            front: 0,
+            back: self.len(),
+        }
+    }
+}
+
+/// An iterator over the key/value pairs in a `MappedMap`.
+pub struct Entries<'a> {
+    map: MappedMap<'a>,
+    front: uint,
+    back: uint,
+}
+
+impl<'a> Iterator<(&'a [u8], &'a [u8])> for Entries<'a> {
+    fn next(&mut self) -> Option<(&'a [u8], &'a [u8])> {
+        if self.front == self.back {
+            None
+        } else {
+            self.front += 1;
+            Some(self.map.entry(self.front - 1))
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<'a> DoubleEndedIterator<(&'a [u8], &'a [u8])> for Entries<'a> {
+    fn next_back(&mut self) -> Option<(&'a [u8], &'a [u8])> {
+        if self.front == self.back {
+            None
+        } else {
+            self.back -= 1;
+            Some(self.map.entry(self.back))
+        }
+    }
+}
+
+impl<'a> ExactSize<(&'a [u8], &'a [u8])> for Entries<'a> {}
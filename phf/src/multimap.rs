@@ -0,0 +1,183 @@
+//! An immutable map allowing repeated keys, constructed at compile time.
+use core::prelude::*;
+use core::fmt;
+use core::slice;
+use shared;
+use shared::{PhfHash, PhfHasher, PhfBorrow, XxHasher};
+
+/// An immutable map allowing repeated keys, constructed at compile time.
+///
+/// The values of each key are stored contiguously, in the order in which they
+/// were defined. Keys are ordered by their first definition.
+///
+/// `MultiMap`s may be created with the `phf_multimap` macro:
+///
+/// ```rust
+/// # #![feature(phase)]
+/// extern crate phf;
+/// #[phase(plugin)]
+/// extern crate phf_mac;
+///
+/// static MIME_TYPES: phf::MultiMap<&'static str, &'static str> = phf_multimap! {
+///    "html" => "text/html",
+///    "html" => "application/xhtml+xml",
+///    "png" => "image/png",
+/// };
+///
+/// # fn main() {
+/// assert_eq!(["text/html", "application/xhtml+xml"][], MIME_TYPES.get_all("html"));
+/// # }
+/// ```
+///
+/// ## Note
+///
+/// The fields of this struct are public so that they may be initialized by the
+/// `phf_multimap` macro. They are subject to change at any time and should
+/// never be accessed directly.
+pub struct MultiMap<K:'static, V:'static, H:'static = XxHasher> {
+    #[doc(hidden)]
+    pub hasher: H,
+    #[doc(hidden)]
+    pub key: u64,
+    #[doc(hidden)]
+    pub disps: &'static [(u32, u32)],
+    #[doc(hidden)]
+    pub idxs: &'static [uint],
+    #[doc(hidden)]
+    pub entries: &'static [(K, uint, uint)],
+    #[doc(hidden)]
+    pub values: &'static [V],
+}
+
+impl<K, V, H> fmt::Show for MultiMap<K, V, H> where K: fmt::Show, V: fmt::Show, H: PhfHasher {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "{{"));
+        let mut first = true;
+        for (k, vs) in self.entries() {
+            if !first {
+                try!(write!(fmt, ", "));
+            }
+            try!(write!(fmt, "{}: {}", k, vs))
+            first = false;
+        }
+        write!(fmt, "}}")
+    }
+}
+
+impl<K, V, H> MultiMap<K, V, H> where K: PhfHash+Eq, H: PhfHasher {
+    /// Returns the values that `key` maps to, in definition order.
+    ///
+    /// The slice is empty if `key` isn't in the `MultiMap`. `key` may be any
+    /// borrowed form of the key type.
+    pub fn get_all<Sized? T>(&self, key: &T) -> &[V] where T: PhfHash+Eq, K: PhfBorrow<T> {
+        match self.get_entry_(key) {
+            Some(&(ref k, start, end)) if key == k.borrow() => self.values[start..end],
+            _ => self.values[0..0],
+        }
+    }
+
+    /// Returns the first value that `key` maps to.
+    pub fn get<Sized? T>(&self, key: &T) -> Option<&V> where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.get_all(key).head()
+    }
+
+    /// Determines if `key` is in the `MultiMap`.
+    pub fn contains_key<Sized? T>(&self, key: &T) -> bool where T: PhfHash+Eq, K: PhfBorrow<T> {
+        !self.get_all(key).is_empty()
+    }
+}
+
+impl<K, V, H> MultiMap<K, V, H> where H: PhfHasher {
+    /// Returns true if the `MultiMap` is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of distinct keys in the `MultiMap`.
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+
+    /// Returns the total number of values in the `MultiMap`.
+    pub fn num_values(&self) -> uint {
+        self.values.len()
+    }
+
+    fn get_entry_<Sized? T>(&self, key: &T) -> Option<&(K, uint, uint)> where T: PhfHash {
+        if self.is_empty() {
+            return None;
+        }
+
+        let (g, f1, f2) = shared::phf_hash(&self.hasher, key, self.key);
+        let (d1, d2) = self.disps[(g % (self.disps.len() as u32)) as uint];
+        let idx = self.idxs[(shared::displace(f1, f2, d1, d2) % (self.idxs.len() as u32)) as uint];
+        Some(&self.entries[idx])
+    }
+
+    /// Returns an iterator over the keys in the map, each with the values it
+    /// maps to.
+    ///
+    /// Keys are returned in the order of their first definition.
+    pub fn entries<'a>(&'a self) -> Entries<'a, K, V> {
+        Entries {
+            iter: self.entries.iter(),
+            values: self.values,
+        }
+    }
+
+    /// Returns an iterator over the distinct keys in the map.
+    ///
+    /// Keys are returned in the order of their first definition.
+    pub fn keys<'a>(&'a self) -> Keys<'a, K, V> {
+        Keys { iter: self.entries() }
+    }
+}
+
+/// An iterator over the keys in a `MultiMap` and their values.
+pub struct Entries<'a, K:'a, V:'a> {
+    iter: slice::Items<'a, (K, uint, uint)>,
+    values: &'a [V],
+}
+
+impl<'a, K, V> Iterator<(&'a K, &'a [V])> for Entries<'a, K, V> {
+    fn next(&mut self) -> Option<(&'a K, &'a [V])> {
+        let values = self.values;
+        self.iter.next().map(|&(ref k, start, end)| (k, values[start..end]))
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator<(&'a K, &'a [V])> for Entries<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a [V])> {
+        let values = self.values;
+        self.iter.next_back().map(|&(ref k, start, end)| (k, values[start..end]))
+    }
+}
+
+impl<'a, K, V> ExactSize<(&'a K, &'a [V])> for Entries<'a, K, V> {}
+
+/// An iterator over the distinct keys in a `MultiMap`.
+pub struct Keys<'a, K:'a, V:'a> {
+    iter: Entries<'a, K, V>,
+}
+
+impl<'a, K, V> Iterator<&'a K> for Keys<'a, K, V> {
+    fn next(&mut self) -> Option<&'a K> {
+        self.iter.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator<&'a K> for Keys<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a K> {
+        self.iter.next_back().map(|(k, _)| k)
+    }
+}
+
+impl<'a, K, V> ExactSize<&'a K> for Keys<'a, K, V> {}
@@ -1,9 +1,12 @@
 //! An order-preserving immutable map constructed at compile time.
 use core::prelude::*;
 use core::fmt;
+use core::hash::{Hash, Writer};
+use core::hash::sip;
 use core::slice;
 use core::iter;
-use PhfHash;
+use core::iter::order;
+use {PhfHash, PhfHasher, PhfBorrow, XxHasher};
 use shared;
 
 /// An order-preserving immutable map constructed at compile time.
@@ -32,7 +35,9 @@ use shared;
 /// The fields of this struct are public so that they may be initialized by the
 /// `phf_ordered_map` macro. They are subject to change at any time and should
 /// never be accessed directly.
-pub struct OrderedMap<K:'static, V:'static> {
+pub struct OrderedMap<K:'static, V:'static, H:'static = XxHasher> {
+    #[doc(hidden)]
+    pub hasher: H,
     #[doc(hidden)]
     pub key: u64,
     #[doc(hidden)]
@@ -43,7 +48,7 @@ pub struct OrderedMap<K:'static, V:'static> {
     pub entries: &'static [(K, V)],
 }
 
-impl<K, V> fmt::Show for OrderedMap<K, V> where K: fmt::Show, V: fmt::Show {
+impl<K, V, H> fmt::Show for OrderedMap<K, V, H> where K: fmt::Show, V: fmt::Show, H: PhfHasher {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         try!(write!(fmt, "{{"));
         let mut first = true;
@@ -58,20 +63,57 @@ impl<K, V> fmt::Show for OrderedMap<K, V> where K: fmt::Show, V: fmt::Show {
     }
 }
 
-impl<K, V> Index<K, V> for OrderedMap<K, V> where K: PhfHash+Eq {
+impl<K, V, H> PartialEq for OrderedMap<K, V, H> where K: PartialEq, V: PartialEq, H: PhfHasher {
+    /// Two `OrderedMap`s are equal if they contain the same entries in the
+    /// same order.
+    fn eq(&self, other: &OrderedMap<K, V, H>) -> bool {
+        order::eq(self.entries(), other.entries())
+    }
+}
+
+impl<K, V, H> Eq for OrderedMap<K, V, H> where K: Eq, V: Eq, H: PhfHasher {}
+
+impl<K, V, H> PartialOrd for OrderedMap<K, V, H> where K: PartialOrd, V: PartialOrd, H: PhfHasher {
+    /// `OrderedMap`s are compared lexicographically by their entries.
+    fn partial_cmp(&self, other: &OrderedMap<K, V, H>) -> Option<Ordering> {
+        order::partial_cmp(self.entries(), other.entries())
+    }
+}
+
+impl<K, V, H> Ord for OrderedMap<K, V, H> where K: Ord, V: Ord, H: PhfHasher {
+    fn cmp(&self, other: &OrderedMap<K, V, H>) -> Ordering {
+        order::cmp(self.entries(), other.entries())
+    }
+}
+
+impl<K, V, H, S> Hash<S> for OrderedMap<K, V, H> where K: Hash, V: Hash, H: PhfHasher, S: Writer {
+    fn hash(&self, state: &mut S) {
+        // The entries are hashed the same way as `Map`'s, but are combined in
+        // order to match `eq`.
+        self.len().hash(state);
+        for entry in self.entries() {
+            sip::hash(entry).hash(state);
+        }
+    }
+}
+
+impl<K, V, H> Index<K, V> for OrderedMap<K, V, H> where K: PhfHash+Eq, H: PhfHasher {
     fn index(&self, k: &K) -> &V {
         self.get(k).expect("invalid key")
     }
 }
 
-impl<K, V> OrderedMap<K, V> where K: PhfHash+Eq {
+impl<K, V, H> OrderedMap<K, V, H> where K: PhfHash+Eq, H: PhfHasher {
     /// Returns a reference to the value that `key` maps to.
-    pub fn get(&self, key: &K) -> Option<&V> {
-        self.get_entry_(key, |k| key == k).map(|(_, e)| &e.1)
+    ///
+    /// `key` may be any borrowed form of the key type, for example a `&str`
+    /// for an `OrderedMap<&'static str, V>`.
+    pub fn get<Sized? T>(&self, key: &T) -> Option<&V> where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.get_entry_(key, |k| key == k.borrow()).map(|(_, e)| &e.1)
     }
 
     /// Determines if `key` is in the `Map`.
-    pub fn contains_key(&self, key: &K) -> bool {
+    pub fn contains_key<Sized? T>(&self, key: &T) -> bool where T: PhfHash+Eq, K: PhfBorrow<T> {
         self.get(key).is_some()
     }
 
@@ -79,18 +121,19 @@ impl<K, V> OrderedMap<K, V> where K: PhfHash+Eq {
     /// key.
     ///
     /// This can be useful for interning schemes.
-    pub fn get_key(&self, key: &K) -> Option<&K> {
-        self.get_entry_(key, |k| k == key).map(|(_, e)| &e.0)
+    pub fn get_key<Sized? T>(&self, key: &T) -> Option<&K> where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.get_entry_(key, |k| key == k.borrow()).map(|(_, e)| &e.0)
     }
 
     /// Returns the index of the key within the list used to initialize
     /// the ordered map.
-    pub fn get_index(&self, key: &K) -> Option<uint> {
-        self.get_entry_(key, |k| k == key).map(|(i, _)| i)
+    pub fn get_index<Sized? T>(&self, key: &T) -> Option<uint>
+            where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.get_entry_(key, |k| key == k.borrow()).map(|(i, _)| i)
     }
 }
 
-impl<K, V> OrderedMap<K, V> {
+impl<K, V, H> OrderedMap<K, V, H> where H: PhfHasher {
     /// Returns true if the `Map` is empty.
     pub fn is_empty(&self) -> bool {
         self.len() == 0
@@ -103,7 +146,11 @@ impl<K, V> OrderedMap<K, V> {
 
     fn get_entry_<Sized? T>(&self, key: &T, check: |&K| -> bool) -> Option<(uint, &(K, V))>
             where T: PhfHash {
-        let (g, f1, f2) = key.phf_hash(self.key);
+        if self.disps.is_empty() {
+            return None;
+        }
+
+        let (g, f1, f2) = shared::phf_hash(&self.hasher, key, self.key);
         let (d1, d2) = self.disps[(g % (self.disps.len() as u32)) as uint];
         let idx = self.idxs[(shared::displace(f1, f2, d1, d2) % (self.idxs.len() as u32)) as uint];
         let entry = &self.entries[idx];
@@ -143,6 +190,24 @@ impl<K, V> OrderedMap<K, V> {
         self.get_entry_(key, |k| key.equiv(k)).map(|(_, e)| (&e.0, &e.1))
     }
 
+    /// Returns the key/value pair at the given index within the list used to
+    /// initialize the ordered map.
+    ///
+    /// This is the inverse of `get_index`.
+    pub fn get_at(&self, index: uint) -> Option<(&K, &V)> {
+        self.entries.get(index).map(|e| (&e.0, &e.1))
+    }
+
+    /// Returns the first key/value pair in the map.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.entries.head().map(|e| (&e.0, &e.1))
+    }
+
+    /// Returns the last key/value pair in the map.
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.entries.last().map(|e| (&e.0, &e.1))
+    }
+
     /// Returns an iterator over the key/value pairs in the map.
     ///
     /// Entries are returned in the same order in which they were defined.
@@ -150,6 +215,17 @@ impl<K, V> OrderedMap<K, V> {
         Entries { iter: self.entries.iter() }
     }
 
+    /// Returns an iterator over the key/value pairs with indices from `start`
+    /// up to but not including `end`.
+    ///
+    /// Returns `None` if `start > end` or if `end > self.len()`.
+    pub fn entries_range<'a>(&'a self, start: uint, end: uint) -> Option<Entries<'a, K, V>> {
+        if start > end || end > self.len() {
+            return None;
+        }
+        Some(Entries { iter: self.entries[start..end].iter() })
+    }
+
     /// Returns an iterator over the keys in the map.
     ///
     /// Keys are returned in the same order in which they were defined.
@@ -157,12 +233,44 @@ impl<K, V> OrderedMap<K, V> {
         Keys { iter: self.entries().map(|e| &e.0) }
     }
 
+    /// Returns an iterator over the keys with indices from `start` up to but
+    /// not including `end`.
+    ///
+    /// Returns `None` if `start > end` or if `end > self.len()`.
+    pub fn keys_range<'a>(&'a self, start: uint, end: uint) -> Option<Keys<'a, K, V>> {
+        self.entries_range(start, end).map(|entries| Keys { iter: entries.map(|e| &e.0) })
+    }
+
+    /// Returns an iterator over the keys in the map, starting at the key with
+    /// the given index.
+    ///
+    /// Returns `None` if `index > self.len()`.
+    pub fn keys_from<'a>(&'a self, index: uint) -> Option<Keys<'a, K, V>> {
+        self.keys_range(index, self.len())
+    }
+
     /// Returns an iterator over the values in the map.
     ///
     /// Values are returned in the same order in which they were defined.
     pub fn values<'a>(&'a self) -> Values<'a, K, V> {
         Values { iter: self.entries().map(|e| &e.1) }
     }
+
+    /// Returns an iterator over the values with indices from `start` up to
+    /// but not including `end`.
+    ///
+    /// Returns `None` if `start > end` or if `end > self.len()`.
+    pub fn values_range<'a>(&'a self, start: uint, end: uint) -> Option<Values<'a, K, V>> {
+        self.entries_range(start, end).map(|entries| Values { iter: entries.map(|e| &e.1) })
+    }
+
+    /// Returns an iterator over the values in the map, starting at the value
+    /// with the given index.
+    ///
+    /// Returns `None` if `index > self.len()`.
+    pub fn values_from<'a>(&'a self, index: uint) -> Option<Values<'a, K, V>> {
+        self.values_range(index, self.len())
+    }
 }
 
 /// An iterator over the entries in a `OrderedMap`.
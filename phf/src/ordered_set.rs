@@ -1,8 +1,10 @@
 //! An order-preserving immutable set constructed at compile time.
 use core::prelude::*;
 use core::fmt;
+use core::hash::{Hash, Writer};
 use ordered_map;
-use {PhfHash, OrderedMap};
+use set::{mod, SetLike, SetValues, Union, Intersection, Difference, SymmetricDifference};
+use {PhfHash, PhfHasher, PhfBorrow, XxHasher, OrderedMap};
 
 /// An order-preserving immutable set constructed at compile time.
 ///
@@ -30,12 +32,12 @@ use {PhfHash, OrderedMap};
 /// The fields of this struct are public so that they may be initialized by the
 /// `phf_ordered_set` macro. They are subject to change at any time and should
 /// never be accessed directly.
-pub struct OrderedSet<T:'static> {
+pub struct OrderedSet<T:'static, H:'static = XxHasher> {
     #[doc(hidden)]
-    pub map: OrderedMap<T, ()>,
+    pub map: OrderedMap<T, (), H>,
 }
 
-impl<T> fmt::Show for OrderedSet<T> where T: fmt::Show {
+impl<T, H> fmt::Show for OrderedSet<T, H> where T: fmt::Show, H: PhfHasher {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         try!(write!(fmt, "{{"));
         let mut first = true;
@@ -50,48 +52,135 @@ impl<T> fmt::Show for OrderedSet<T> where T: fmt::Show {
     }
 }
 
-impl<T: PhfHash+Eq> OrderedSet<T> {
+impl<T, H> PartialEq for OrderedSet<T, H> where T: PartialEq, H: PhfHasher {
+    /// Two `OrderedSet`s are equal if they contain the same values in the same
+    /// order.
+    fn eq(&self, other: &OrderedSet<T, H>) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<T, H> Eq for OrderedSet<T, H> where T: Eq, H: PhfHasher {}
+
+impl<T, H> PartialOrd for OrderedSet<T, H> where T: PartialOrd, H: PhfHasher {
+    /// `OrderedSet`s are compared lexicographically by their values.
+    fn partial_cmp(&self, other: &OrderedSet<T, H>) -> Option<Ordering> {
+        self.map.partial_cmp(&other.map)
+    }
+}
+
+impl<T, H> Ord for OrderedSet<T, H> where T: Ord, H: PhfHasher {
+    fn cmp(&self, other: &OrderedSet<T, H>) -> Ordering {
+        self.map.cmp(&other.map)
+    }
+}
+
+impl<T, H, S> Hash<S> for OrderedSet<T, H> where T: Hash, H: PhfHasher, S: Writer {
+    fn hash(&self, state: &mut S) {
+        self.map.hash(state)
+    }
+}
+
+impl<T, H> OrderedSet<T, H> where T: PhfHash+Eq, H: PhfHasher {
     /// Returns a reference to the set's internal static instance of the given
     /// key.
     ///
     /// This can be useful for interning schemes.
     #[inline]
-    pub fn get_key(&self, key: &T) -> Option<&T> {
+    pub fn get_key<Sized? U>(&self, key: &U) -> Option<&T> where U: PhfHash+Eq, T: PhfBorrow<U> {
         self.map.get_key(key)
     }
 
     /// Returns the index of the key within the list used to initialize
     /// the ordered set.
-    pub fn get_index(&self, key: &T) -> Option<uint> {
+    pub fn get_index<Sized? U>(&self, key: &U) -> Option<uint>
+            where U: PhfHash+Eq, T: PhfBorrow<U> {
         self.map.get_index(key)
     }
 
     /// Returns true if `value` is in the `Set`.
+    ///
+    /// `value` may be any borrowed form of the set's value type.
     #[inline]
-    pub fn contains(&self, value: &T) -> bool {
+    pub fn contains<Sized? U>(&self, value: &U) -> bool where U: PhfHash+Eq, T: PhfBorrow<U> {
         self.map.contains_key(value)
     }
 
     /// Returns true if `other` shares no elements with `self`.
     #[inline]
-    pub fn is_disjoint(&self, other: &OrderedSet<T>) -> bool {
+    pub fn is_disjoint<S>(&self, other: &S) -> bool where S: SetLike<T> {
         !self.iter().any(|value| other.contains(value))
     }
 
     /// Returns true if `other` contains all values in `self`.
     #[inline]
-    pub fn is_subset(&self, other: &OrderedSet<T>) -> bool {
+    pub fn is_subset<S>(&self, other: &S) -> bool where S: SetLike<T> {
         self.iter().all(|value| other.contains(value))
     }
 
     /// Returns true if `self` contains all values in `other`.
     #[inline]
-    pub fn is_superset(&self, other: &OrderedSet<T>) -> bool {
-        other.is_subset(self)
+    pub fn is_superset<'a, S, I>(&self, other: &'a S) -> bool
+            where S: SetValues<'a, T, I>, I: Iterator<&'a T> {
+        other.values().all(|value| self.contains(value))
+    }
+
+    /// Returns an iterator over the values in `self` or `other`, without
+    /// duplicates.
+    ///
+    /// The values of `self` are returned first in definition order, followed
+    /// by those values of `other` which aren't in `self`.
+    #[inline]
+    pub fn union<'a, S, I>(&'a self, other: &'a S)
+                           -> Union<'a, T, Entries<'a, T>, I, OrderedSet<T, H>>
+            where S: SetValues<'a, T, I>, I: Iterator<&'a T> {
+        set::union(self.iter(), self, other)
+    }
+
+    /// Returns an iterator over the values in both `self` and `other`, in
+    /// definition order.
+    #[inline]
+    pub fn intersection<'a, S>(&'a self, other: &'a S) -> Intersection<'a, T, Entries<'a, T>, S>
+            where S: SetLike<T> {
+        set::intersection(self.iter(), other)
+    }
+
+    /// Returns an iterator over the values in `self` which aren't in `other`,
+    /// in definition order.
+    #[inline]
+    pub fn difference<'a, S>(&'a self, other: &'a S) -> Difference<'a, T, Entries<'a, T>, S>
+            where S: SetLike<T> {
+        set::difference(self.iter(), other)
+    }
+
+    /// Returns an iterator over the values in exactly one of `self` and
+    /// `other`.
+    ///
+    /// The values of `self` are returned first, in definition order.
+    #[inline]
+    pub fn symmetric_difference<'a, S, I>(&'a self, other: &'a S)
+            -> SymmetricDifference<'a, T, Entries<'a, T>, I, OrderedSet<T, H>, S>
+            where S: SetValues<'a, T, I>, I: Iterator<&'a T> {
+        set::symmetric_difference(self.iter(), self, other)
     }
 }
 
-impl<T> OrderedSet<T> {
+impl<T, H> SetLike<T> for OrderedSet<T, H> where T: PhfHash+Eq, H: PhfHasher {
+    #[inline]
+    fn contains(&self, value: &T) -> bool {
+        self.map.contains_key(value)
+    }
+}
+
+impl<'a, T, H> SetValues<'a, T, Entries<'a, T>> for OrderedSet<T, H>
+        where T: PhfHash+Eq, H: PhfHasher {
+    #[inline]
+    fn values(&'a self) -> Entries<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, H> OrderedSet<T, H> where H: PhfHasher {
     /// Returns the number of elements in the `Set`.
     #[inline]
     pub fn len(&self) -> uint {
@@ -124,6 +213,27 @@ impl<T> OrderedSet<T> {
         self.map.get_index_equiv(key)
     }
 
+    /// Returns the value at the given index within the list used to
+    /// initialize the ordered set.
+    ///
+    /// This is the inverse of `get_index`.
+    #[inline]
+    pub fn get_at(&self, index: uint) -> Option<&T> {
+        self.map.get_at(index).map(|(k, _)| k)
+    }
+
+    /// Returns the first value in the set.
+    #[inline]
+    pub fn first(&self) -> Option<&T> {
+        self.map.first().map(|(k, _)| k)
+    }
+
+    /// Returns the last value in the set.
+    #[inline]
+    pub fn last(&self) -> Option<&T> {
+        self.map.last().map(|(k, _)| k)
+    }
+
     /// Returns an iterator over the values in the set.
     ///
     /// Values are returned in the same order in which they were defined.
@@ -131,6 +241,24 @@ impl<T> OrderedSet<T> {
     pub fn iter<'a>(&'a self) -> Entries<'a, T> {
         Entries { iter: self.map.keys() }
     }
+
+    /// Returns an iterator over the values with indices from `start` up to
+    /// but not including `end`.
+    ///
+    /// Returns `None` if `start > end` or if `end > self.len()`.
+    #[inline]
+    pub fn iter_range<'a>(&'a self, start: uint, end: uint) -> Option<Entries<'a, T>> {
+        self.map.keys_range(start, end).map(|keys| Entries { iter: keys })
+    }
+
+    /// Returns an iterator over the values in the set, starting at the value
+    /// with the given index.
+    ///
+    /// Returns `None` if `index > self.len()`.
+    #[inline]
+    pub fn iter_from<'a>(&'a self, index: uint) -> Option<Entries<'a, T>> {
+        self.map.keys_from(index).map(|keys| Entries { iter: keys })
+    }
 }
 
 /// An iterator over the values in a `OrderedSet`.
@@ -0,0 +1,139 @@
+//! An immutable map supporting prefix lookups, constructed at compile time.
+use core::prelude::*;
+use core::fmt;
+use core::slice;
+use shared::{PhfHasher, XxHasher};
+use map;
+use Map;
+
+/// An immutable map from strings supporting prefix lookups, constructed at
+/// compile time.
+///
+/// In addition to exact lookups, a `PrefixMap` can find the entries whose
+/// keys are prefixes of a string. Each lookup hashes one candidate prefix for
+/// each distinct key length.
+///
+/// `PrefixMap`s may be created with the `phf_prefix_map` macro:
+///
+/// ```rust
+/// # #![feature(phase)]
+/// extern crate phf;
+/// #[phase(plugin)]
+/// extern crate phf_mac;
+///
+/// static ROUTES: phf::PrefixMap<int> = phf_prefix_map! {
+///    "/api" => 1,
+///    "/api/v2" => 2,
+/// };
+///
+/// # fn main() {
+/// assert_eq!(Some(("/api/v2", &2)), ROUTES.longest_prefix("/api/v2/users/7"));
+/// # }
+/// ```
+///
+/// ## Note
+///
+/// The fields of this struct are public so that they may be initialized by the
+/// `phf_prefix_map` macro. They are subject to change at any time and should
+/// never be accessed directly.
+pub struct PrefixMap<V:'static, H:'static = XxHasher> {
+    #[doc(hidden)]
+    pub map: Map<&'static str, V, H>,
+    #[doc(hidden)]
+    pub lengths: &'static [uint],
+}
+
+impl<V, H> fmt::Show for PrefixMap<V, H> where V: fmt::Show, H: PhfHasher {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.map)
+    }
+}
+
+impl<V, H> PrefixMap<V, H> where H: PhfHasher {
+    /// Returns a reference to the value that `key` maps to.
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    /// Determines if `key` is in the `PrefixMap`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Returns the entry whose key is the longest prefix of `s`.
+    ///
+    /// The key is returned as the matching prefix of `s`.
+    pub fn longest_prefix<'a>(&'a self, s: &'a str) -> Option<(&'a str, &'a V)> {
+        for &len in self.lengths.iter().rev() {
+            match self.get_prefix(s, len) {
+                Some(entry) => return Some(entry),
+                None => {}
+            }
+        }
+        None
+    }
+
+    /// Returns an iterator over the entries whose keys are prefixes of `s`.
+    ///
+    /// Entries are returned from the shortest key to the longest, with each
+    /// key returned as the matching prefix of `s`.
+    pub fn prefixes_of<'a>(&'a self, s: &'a str) -> PrefixesOf<'a, V, H> {
+        PrefixesOf {
+            map: self,
+            s: s,
+            lengths: self.lengths.iter(),
+        }
+    }
+
+    fn get_prefix<'a>(&'a self, s: &'a str, len: uint) -> Option<(&'a str, &'a V)> {
+        if len > s.len() || !s.is_char_boundary(len) {
+            return None;
+        }
+        let prefix = s[..len];
+        self.map.get(prefix).map(|v| (prefix, v))
+    }
+
+    /// Returns true if the `PrefixMap` is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries in the `PrefixMap`.
+    pub fn len(&self) -> uint {
+        self.map.len()
+    }
+
+    /// Returns an iterator over the key/value pairs in the map.
+    ///
+    /// Entries are returned in an arbitrary but fixed order.
+    pub fn entries<'a>(&'a self) -> map::Entries<'a, &'static str, V> {
+        self.map.entries()
+    }
+}
+
+/// An iterator over the entries in a `PrefixMap` whose keys are prefixes of a
+/// string.
+pub struct PrefixesOf<'a, V:'static, H:'static> {
+    map: &'a PrefixMap<V, H>,
+    s: &'a str,
+    lengths: slice::Items<'a, uint>,
+}
+
+impl<'a, V, H> Iterator<(&'a str, &'a V)> for PrefixesOf<'a, V, H> where H: PhfHasher {
+    fn next(&mut self) -> Option<(&'a str, &'a V)> {
+        loop {
+            let len = match self.lengths.next() {
+                Some(&len) if len <= self.s.len() => len,
+                _ => return None,
+            };
+            match self.map.get_prefix(self.s, len) {
+                Some(entry) => return Some(entry),
+                None => {}
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (0, self.lengths.size_hint().1)
+    }
+}
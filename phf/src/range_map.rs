@@ -0,0 +1,136 @@
+//! An immutable map from integer ranges, constructed at compile time.
+use core::prelude::*;
+use core::fmt;
+use core::slice;
+
+/// An immutable map from non-overlapping integer ranges to values,
+/// constructed at compile time.
+///
+/// Ranges are checked for overlaps and sorted when the map is built, and
+/// lookups binary search them.
+///
+/// `RangeMap`s may be created with the `phf_range_map` macro. Ranges written
+/// `a..b` exclude their upper bound, while those written `a...b` include it:
+///
+/// ```rust
+/// # #![feature(phase)]
+/// extern crate phf;
+/// #[phase(plugin)]
+/// extern crate phf_mac;
+///
+/// static CLASSES: phf::RangeMap<u16, &'static str> = phf_range_map! {
+///    200u16..300u16 => "success",
+///    400u16...499u16 => "client error",
+/// };
+///
+/// # fn main() {
+/// assert_eq!(Some(&"client error"), CLASSES.get(&404));
+/// assert_eq!(None, CLASSES.get(&300));
+/// # }
+/// ```
+///
+/// ## Note
+///
+/// The fields of this struct are public so that they may be initialized by the
+/// `phf_range_map` macro. They are subject to change at any time and should
+/// never be accessed directly.
+pub struct RangeMap<K:'static, V:'static> {
+    #[doc(hidden)]
+    pub entries: &'static [(K, K, V)],
+}
+
+impl<K, V> fmt::Show for RangeMap<K, V> where K: fmt::Show, V: fmt::Show {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(fmt, "{{"));
+        let mut first = true;
+        for &(ref start, ref end, ref v) in self.entries() {
+            if !first {
+                try!(write!(fmt, ", "));
+            }
+            try!(write!(fmt, "{}...{}: {}", start, end, v))
+            first = false;
+        }
+        write!(fmt, "}}")
+    }
+}
+
+impl<K, V> RangeMap<K, V> where K: Ord {
+    /// Returns a reference to the value of the range containing `point`.
+    pub fn get(&self, point: &K) -> Option<&V> {
+        self.get_range(point).map(|(_, _, v)| v)
+    }
+
+    /// Determines if `point` is in one of the ranges of the `RangeMap`.
+    pub fn contains(&self, point: &K) -> bool {
+        self.get_range(point).is_some()
+    }
+
+    /// Returns the range containing `point` along with its value.
+    ///
+    /// The bounds of the range are both inclusive.
+    pub fn get_range(&self, point: &K) -> Option<(&K, &K, &V)> {
+        // Find the number of ranges starting at or before `point`.
+        let (mut lo, mut hi) = (0, self.entries.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.entries[mid].0 <= *point {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        if lo == 0 {
+            return None;
+        }
+        let &(ref start, ref end, ref v) = &self.entries[lo - 1];
+        if *point <= *end {
+            Some((start, end, v))
+        } else {
+            None
+        }
+    }
+}
+
+impl<K, V> RangeMap<K, V> {
+    /// Returns true if the `RangeMap` is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of ranges in the `RangeMap`.
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+
+    /// Returns an iterator over the ranges in the map, as tuples of their
+    /// inclusive bounds and their values.
+    ///
+    /// Ranges are returned in ascending order.
+    pub fn entries<'a>(&'a self) -> Entries<'a, K, V> {
+        Entries { iter: self.entries.iter() }
+    }
+}
+
+/// An iterator over the ranges in a `RangeMap`.
+pub struct Entries<'a, K:'a, V:'a> {
+    iter: slice::Items<'a, (K, K, V)>,
+}
+
+impl<'a, K, V> Iterator<&'a (K, K, V)> for Entries<'a, K, V> {
+    fn next(&mut self) -> Option<&'a (K, K, V)> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator<&'a (K, K, V)> for Entries<'a, K, V> {
+    fn next_back(&mut self) -> Option<&'a (K, K, V)> {
+        self.iter.next_back()
+    }
+}
+
+impl<'a, K, V> ExactSize<&'a (K, K, V)> for Entries<'a, K, V> {}
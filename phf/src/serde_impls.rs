@@ -0,0 +1,36 @@
+//! `Serialize` implementations, enabled by the `serde` feature.
+use core::prelude::*;
+use serde::{Serialize, Serializer};
+use {PhfHasher, Map, Set, OrderedMap, OrderedSet};
+
+/// Serializes as a map, in the `Map`'s fixed internal order.
+impl<K, V, H, S, E> Serialize<S, E> for Map<K, V, H>
+        where K: Serialize<S, E>, V: Serialize<S, E>, H: PhfHasher, S: Serializer<E> {
+    fn serialize(&self, s: &mut S) -> Result<(), E> {
+        s.serialize_map(self.entries().map(|&(ref k, ref v)| (k, v)))
+    }
+}
+
+/// Serializes as a sequence, in the `Set`'s fixed internal order.
+impl<T, H, S, E> Serialize<S, E> for Set<T, H>
+        where T: Serialize<S, E>, H: PhfHasher, S: Serializer<E> {
+    fn serialize(&self, s: &mut S) -> Result<(), E> {
+        s.serialize_seq(self.iter())
+    }
+}
+
+/// Serializes as a map, in definition order.
+impl<K, V, H, S, E> Serialize<S, E> for OrderedMap<K, V, H>
+        where K: Serialize<S, E>, V: Serialize<S, E>, H: PhfHasher, S: Serializer<E> {
+    fn serialize(&self, s: &mut S) -> Result<(), E> {
+        s.serialize_map(self.entries().map(|&(ref k, ref v)| (k, v)))
+    }
+}
+
+/// Serializes as a sequence, in definition order.
+impl<T, H, S, E> Serialize<S, E> for OrderedSet<T, H>
+        where T: Serialize<S, E>, H: PhfHasher, S: Serializer<E> {
+    fn serialize(&self, s: &mut S) -> Result<(), E> {
+        s.serialize_seq(self.iter())
+    }
+}
@@ -2,7 +2,10 @@
 use core::prelude::*;
 use Map;
 use core::fmt;
-use shared::PhfHash;
+use core::hash::{Hash, Writer};
+use core::iter;
+use collections::btree_set::{mod, BTreeSet};
+use shared::{PhfHash, PhfHasher, PhfBorrow, XxHasher};
 use map;
 
 /// An immutable set constructed at compile time.
@@ -28,12 +31,12 @@ use map;
 /// The fields of this struct are public so that they may be initialized by the
 /// `phf_set` macro. They are subject to change at any time and should never be
 /// accessed directly.
-pub struct Set<T:'static> {
+pub struct Set<T:'static, H:'static = XxHasher> {
     #[doc(hidden)]
-    pub map: Map<T, ()>
+    pub map: Map<T, (), H>
 }
 
-impl<T> fmt::Show for Set<T> where T: fmt::Show {
+impl<T, H> fmt::Show for Set<T, H> where T: fmt::Show, H: PhfHasher {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         try!(write!(fmt, "{{"));
         let mut first = true;
@@ -48,42 +51,111 @@ impl<T> fmt::Show for Set<T> where T: fmt::Show {
     }
 }
 
-impl<T> Set<T> where T: PhfHash+Eq {
+impl<T, H> PartialEq for Set<T, H> where T: PhfHash+Eq, H: PhfHasher {
+    /// Two `Set`s are equal if they contain the same values, regardless of the
+    /// order in which they were defined.
+    fn eq(&self, other: &Set<T, H>) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<T, H> Eq for Set<T, H> where T: PhfHash+Eq, H: PhfHasher {}
+
+impl<T, H, S> Hash<S> for Set<T, H> where T: Hash, H: PhfHasher, S: Writer {
+    fn hash(&self, state: &mut S) {
+        self.map.hash(state)
+    }
+}
+
+impl<T, H> Set<T, H> where T: PhfHash+Eq, H: PhfHasher {
     /// Returns a reference to the set's internal static instance of the given
     /// key.
     ///
     /// This can be useful for interning schemes.
     #[inline]
-    pub fn get_key(&self, key: &T) -> Option<&T> {
+    pub fn get_key<Sized? U>(&self, key: &U) -> Option<&T> where U: PhfHash+Eq, T: PhfBorrow<U> {
         self.map.get_key(key)
     }
 
     /// Returns true if `value` is in the `Set`.
+    ///
+    /// `value` may be any borrowed form of the set's value type.
     #[inline]
-    pub fn contains(&self, value: &T) -> bool {
+    pub fn contains<Sized? U>(&self, value: &U) -> bool where U: PhfHash+Eq, T: PhfBorrow<U> {
         self.map.contains_key(value)
     }
 
     /// Returns true if `other` shares no elements with `self`.
     #[inline]
-    pub fn is_disjoint(&self, other: &Set<T>) -> bool {
+    pub fn is_disjoint<S>(&self, other: &S) -> bool where S: SetLike<T> {
         !self.iter().any(|value| other.contains(value))
     }
 
     /// Returns true if `other` contains all values in `self`.
     #[inline]
-    pub fn is_subset(&self, other: &Set<T>) -> bool {
+    pub fn is_subset<S>(&self, other: &S) -> bool where S: SetLike<T> {
         self.iter().all(|value| other.contains(value))
     }
 
     /// Returns true if `self` contains all values in `other`.
     #[inline]
-    pub fn is_superset(&self, other: &Set<T>) -> bool {
-        other.is_subset(self)
+    pub fn is_superset<'a, S, I>(&self, other: &'a S) -> bool
+            where S: SetValues<'a, T, I>, I: Iterator<&'a T> {
+        other.values().all(|value| self.contains(value))
+    }
+
+    /// Returns an iterator over the values in `self` or `other`, without
+    /// duplicates.
+    ///
+    /// The values of `self` are returned first, followed by those values of
+    /// `other` which aren't in `self`.
+    #[inline]
+    pub fn union<'a, S, I>(&'a self, other: &'a S) -> Union<'a, T, Items<'a, T>, I, Set<T, H>>
+            where S: SetValues<'a, T, I>, I: Iterator<&'a T> {
+        union(self.iter(), self, other)
+    }
+
+    /// Returns an iterator over the values in both `self` and `other`.
+    #[inline]
+    pub fn intersection<'a, S>(&'a self, other: &'a S) -> Intersection<'a, T, Items<'a, T>, S>
+            where S: SetLike<T> {
+        intersection(self.iter(), other)
+    }
+
+    /// Returns an iterator over the values in `self` which aren't in `other`.
+    #[inline]
+    pub fn difference<'a, S>(&'a self, other: &'a S) -> Difference<'a, T, Items<'a, T>, S>
+            where S: SetLike<T> {
+        difference(self.iter(), other)
+    }
+
+    /// Returns an iterator over the values in exactly one of `self` and
+    /// `other`.
+    ///
+    /// The values of `self` are returned first.
+    #[inline]
+    pub fn symmetric_difference<'a, S, I>(&'a self, other: &'a S)
+            -> SymmetricDifference<'a, T, Items<'a, T>, I, Set<T, H>, S>
+            where S: SetValues<'a, T, I>, I: Iterator<&'a T> {
+        symmetric_difference(self.iter(), self, other)
     }
 }
 
-impl<T> Set<T> {
+impl<T, H> SetLike<T> for Set<T, H> where T: PhfHash+Eq, H: PhfHasher {
+    #[inline]
+    fn contains(&self, value: &T) -> bool {
+        self.map.contains_key(value)
+    }
+}
+
+impl<'a, T, H> SetValues<'a, T, Items<'a, T>> for Set<T, H> where T: PhfHash+Eq, H: PhfHasher {
+    #[inline]
+    fn values(&'a self) -> Items<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, H> Set<T, H> where H: PhfHasher {
     /// Returns the number of elements in the `Set`.
     #[inline]
     pub fn len(&self) -> uint {
@@ -111,7 +183,7 @@ impl<T> Set<T> {
     }
 }
 
-impl<T> Set<T> {
+impl<T, H> Set<T, H> where H: PhfHasher {
     /// Returns an iterator over the values in the set.
     ///
     /// Values are returned in an arbitrary but fixed order.
@@ -144,4 +216,179 @@ impl<'a, T> DoubleEndedIterator<&'a T> for Items<'a, T> {
 
 impl<'a, T> ExactSize<&'a T> for Items<'a, T> {}
 
+/// A set which can be combined with `Set`s and `OrderedSet`s.
+///
+/// Implementing `SetLike` for another set type allows it to be the other
+/// operand of `is_disjoint`, `is_subset`, `intersection` and `difference` on
+/// `Set` and `OrderedSet`. The remaining set operations also iterate over
+/// the other operand, which requires `SetValues` as well. Both traits are
+/// implemented for `BTreeSet`.
+///
+/// `HashSet` is defined in `std`, which this crate doesn't depend on, so it
+/// has to be wrapped in a newtype to implement the traits:
+///
+/// ```rust
+/// # #![feature(phase)]
+/// extern crate phf;
+/// #[phase(plugin)]
+/// extern crate phf_mac;
+///
+/// use std::collections::HashSet;
+/// use std::collections::hash_set::SetItems;
+/// use phf::{SetLike, SetValues};
+///
+/// struct Wrapper(HashSet<u32>);
+///
+/// impl SetLike<u32> for Wrapper {
+///     fn contains(&self, value: &u32) -> bool {
+///         self.0.contains(value)
+///     }
+/// }
+///
+/// impl<'a> SetValues<'a, u32, SetItems<'a, u32>> for Wrapper {
+///     fn values(&'a self) -> SetItems<'a, u32> {
+///         self.0.iter()
+///     }
+/// }
+///
+/// static SET: phf::Set<u32> = phf_set! { 1u32, 2u32 };
+///
+/// # fn main() {
+/// let other = Wrapper(vec![2u32, 3].into_iter().collect());
+/// assert_eq!(3, SET.union(&other).count());
+/// # }
+/// ```
+pub trait SetLike<T> {
+    /// Returns true if `value` is in the set.
+    fn contains(&self, value: &T) -> bool;
+}
+
+/// A set whose values can be iterated over by the set operations of `Set`
+/// and `OrderedSet`.
+///
+/// `I` is the type of the iterator over the values.
+pub trait SetValues<'a, T: 'a, I: Iterator<&'a T>>: SetLike<T> {
+    /// Returns an iterator over the values in the set.
+    fn values(&'a self) -> I;
+}
+
+impl<T> SetLike<T> for BTreeSet<T> where T: Ord {
+    #[inline]
+    fn contains(&self, value: &T) -> bool {
+        self.contains(value)
+    }
+}
+
+impl<'a, T> SetValues<'a, T, btree_set::Items<'a, T>> for BTreeSet<T> where T: Ord {
+    #[inline]
+    fn values(&'a self) -> btree_set::Items<'a, T> {
+        self.iter()
+    }
+}
+
+/// A lazy iterator over the values of a set which are also in another.
+pub struct Intersection<'a, T:'a, I, S:'a> {
+    iter: I,
+    other: &'a S,
+}
+
+impl<'a, T, I, S> Iterator<&'a T> for Intersection<'a, T, I, S>
+        where I: Iterator<&'a T>, S: SetLike<T> {
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            match self.iter.next() {
+                Some(value) if self.other.contains(value) => return Some(value),
+                Some(_) => {}
+                None => return None,
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+/// A lazy iterator over the values of a set which aren't in another.
+pub struct Difference<'a, T:'a, I, S:'a> {
+    iter: I,
+    other: &'a S,
+}
+
+impl<'a, T, I, S> Iterator<&'a T> for Difference<'a, T, I, S>
+        where I: Iterator<&'a T>, S: SetLike<T> {
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            match self.iter.next() {
+                Some(value) if !self.other.contains(value) => return Some(value),
+                Some(_) => {}
+                None => return None,
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+/// A lazy iterator over the values in either of two sets.
+pub struct Union<'a, T:'a, I, J, A:'a> {
+    iter: iter::Chain<I, Difference<'a, T, J, A>>,
+}
 
+impl<'a, T, I, J, A> Iterator<&'a T> for Union<'a, T, I, J, A>
+        where I: Iterator<&'a T>, J: Iterator<&'a T>, A: SetLike<T> {
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        self.iter.size_hint()
+    }
+}
+
+/// A lazy iterator over the values in exactly one of two sets.
+pub struct SymmetricDifference<'a, T:'a, I, J, A:'a, S:'a> {
+    iter: iter::Chain<Difference<'a, T, I, S>, Difference<'a, T, J, A>>,
+}
+
+impl<'a, T, I, J, A, S> Iterator<&'a T> for SymmetricDifference<'a, T, I, J, A, S>
+        where I: Iterator<&'a T>, J: Iterator<&'a T>, A: SetLike<T>, S: SetLike<T> {
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        self.iter.size_hint()
+    }
+}
+
+#[doc(hidden)]
+pub fn intersection<'a, T, I, S>(iter: I, other: &'a S) -> Intersection<'a, T, I, S>
+        where I: Iterator<&'a T>, S: SetLike<T> {
+    Intersection { iter: iter, other: other }
+}
+
+#[doc(hidden)]
+pub fn difference<'a, T, I, S>(iter: I, other: &'a S) -> Difference<'a, T, I, S>
+        where I: Iterator<&'a T>, S: SetLike<T> {
+    Difference { iter: iter, other: other }
+}
+
+// `iter` must yield the values of `set`.
+#[doc(hidden)]
+pub fn union<'a, T, I, J, A, S>(iter: I, set: &'a A, other: &'a S) -> Union<'a, T, I, J, A>
+        where I: Iterator<&'a T>, J: Iterator<&'a T>, A: SetLike<T>, S: SetValues<'a, T, J> {
+    Union { iter: iter.chain(Difference { iter: other.values(), other: set }) }
+}
+
+// `iter` must yield the values of `set`.
+#[doc(hidden)]
+pub fn symmetric_difference<'a, T, I, J, A, S>(iter: I, set: &'a A, other: &'a S)
+                                               -> SymmetricDifference<'a, T, I, J, A, S>
+        where I: Iterator<&'a T>, J: Iterator<&'a T>, A: SetLike<T>, S: SetValues<'a, T, J> {
+    let left = Difference { iter: iter, other: other };
+    let right = Difference { iter: other.values(), other: set };
+    SymmetricDifference { iter: left.chain(right) }
+}
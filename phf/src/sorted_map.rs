@@ -0,0 +1,169 @@
+//! An immutable map sorted by key, constructed at compile time.
+use core::prelude::*;
+use core::fmt;
+use ordered_map;
+use {PhfHash, PhfHasher, PhfBorrow, XxHasher, OrderedMap};
+
+/// An immutable map sorted by key, constructed at compile time.
+///
+/// Entries are sorted when the map is built, so in addition to the constant
+/// time lookups of a `Map`, a `SortedMap` supports range queries and finding
+/// the nearest keys to a value, which take logarithmic time.
+///
+/// `SortedMap`s may be created with the `phf_sorted_map` macro:
+///
+/// ```rust
+/// # #![feature(phase)]
+/// extern crate phf;
+/// #[phase(plugin)]
+/// extern crate phf_mac;
+///
+/// static LIMITS: phf::SortedMap<u32, &'static str> = phf_sorted_map! {
+///    1000u32 => "small",
+///    10u32 => "tiny",
+///    100000u32 => "large",
+/// };
+///
+/// # fn main() {
+/// assert_eq!(Some((&1000, &"small")), LIMITS.ceiling(&500u32));
+/// # }
+/// ```
+///
+/// Keys may be strings, byte strings, chars, bools or integers of a single
+/// type, or tuples and arrays of those.
+///
+/// ## Note
+///
+/// The fields of this struct are public so that they may be initialized by the
+/// `phf_sorted_map` macro. They are subject to change at any time and should
+/// never be accessed directly.
+pub struct SortedMap<K:'static, V:'static, H:'static = XxHasher> {
+    #[doc(hidden)]
+    pub map: OrderedMap<K, V, H>,
+}
+
+impl<K, V, H> fmt::Show for SortedMap<K, V, H> where K: fmt::Show, V: fmt::Show, H: PhfHasher {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.map)
+    }
+}
+
+impl<K, V, H> Index<K, V> for SortedMap<K, V, H> where K: PhfHash+Eq, H: PhfHasher {
+    fn index(&self, k: &K) -> &V {
+        self.get(k).expect("invalid key")
+    }
+}
+
+impl<K, V, H> SortedMap<K, V, H> where K: PhfHash+Eq, H: PhfHasher {
+    /// Returns a reference to the value that `key` maps to.
+    ///
+    /// `key` may be any borrowed form of the key type.
+    pub fn get<Sized? T>(&self, key: &T) -> Option<&V> where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.map.get(key)
+    }
+
+    /// Determines if `key` is in the `SortedMap`.
+    pub fn contains_key<Sized? T>(&self, key: &T) -> bool where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.map.contains_key(key)
+    }
+
+    /// Returns the position of `key` in the `SortedMap`.
+    pub fn get_index<Sized? T>(&self, key: &T) -> Option<uint>
+            where T: PhfHash+Eq, K: PhfBorrow<T> {
+        self.map.get_index(key)
+    }
+}
+
+impl<K, V, H> SortedMap<K, V, H> where K: Ord, H: PhfHasher {
+    /// Returns an iterator over the entries whose keys are at least `lo` and
+    /// less than `hi`, in sorted order.
+    pub fn range<'a, Sized? T>(&'a self, lo: &T, hi: &T) -> ordered_map::Entries<'a, K, V>
+            where T: Ord, K: PhfBorrow<T> {
+        let start = self.bound(lo, false);
+        let end = self.bound(hi, false);
+        // Both bounds are at most `self.len()`, so the range is always valid.
+        self.map.entries_range(start, if end < start { start } else { end }).unwrap()
+    }
+
+    /// Returns the entry with the greatest key less than or equal to `key`.
+    pub fn floor<Sized? T>(&self, key: &T) -> Option<(&K, &V)> where T: Ord, K: PhfBorrow<T> {
+        match self.bound(key, true) {
+            0 => None,
+            n => self.map.get_at(n - 1),
+        }
+    }
+
+    /// Returns the entry with the least key greater than or equal to `key`.
+    pub fn ceiling<Sized? T>(&self, key: &T) -> Option<(&K, &V)> where T: Ord, K: PhfBorrow<T> {
+        self.map.get_at(self.bound(key, false))
+    }
+
+    // Returns the number of keys less than `key`, or less than or equal to it
+    // if `inclusive` is set.
+    fn bound<Sized? T>(&self, key: &T, inclusive: bool) -> uint where T: Ord, K: PhfBorrow<T> {
+        let entries = self.map.entries;
+        let (mut lo, mut hi) = (0, entries.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let below = match entries[mid].0.borrow().cmp(key) {
+                Less => true,
+                Equal => inclusive,
+                Greater => false,
+            };
+            if below {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+}
+
+impl<K, V, H> SortedMap<K, V, H> where H: PhfHasher {
+    /// Returns true if the `SortedMap` is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries in the `SortedMap`.
+    pub fn len(&self) -> uint {
+        self.map.len()
+    }
+
+    /// Returns the entry with the least key.
+    pub fn min(&self) -> Option<(&K, &V)> {
+        self.map.first()
+    }
+
+    /// Returns the entry with the greatest key.
+    pub fn max(&self) -> Option<(&K, &V)> {
+        self.map.last()
+    }
+
+    /// Returns the entry at the given position in the `SortedMap`.
+    pub fn get_at(&self, index: uint) -> Option<(&K, &V)> {
+        self.map.get_at(index)
+    }
+
+    /// Returns an iterator over the key/value pairs in the map.
+    ///
+    /// Entries are returned in sorted order.
+    pub fn entries<'a>(&'a self) -> ordered_map::Entries<'a, K, V> {
+        self.map.entries()
+    }
+
+    /// Returns an iterator over the keys in the map.
+    ///
+    /// Keys are returned in sorted order.
+    pub fn keys<'a>(&'a self) -> ordered_map::Keys<'a, K, V> {
+        self.map.keys()
+    }
+
+    /// Returns an iterator over the values in the map.
+    ///
+    /// Values are returned in the order of their keys.
+    pub fn values<'a>(&'a self) -> ordered_map::Values<'a, K, V> {
+        self.map.values()
+    }
+}
@@ -0,0 +1,17 @@
+#![feature(phase)]
+
+#[phase(plugin)]
+extern crate phf_mac;
+extern crate phf;
+
+// A single bucket of 32 keys can't be placed in a table of 32 slots with one
+// hash key, so generation runs out of attempts.
+static SET: phf::Set<u32> = phf_set! { //~ ERROR unable to generate a perfect hash after 1 attempts
+    #[lambda = 32, load_factor = 1.0, max_attempts = 1]
+    0u32, 1u32, 2u32, 3u32, 4u32, 5u32, 6u32, 7u32,
+    8u32, 9u32, 10u32, 11u32, 12u32, 13u32, 14u32, 15u32,
+    16u32, 17u32, 18u32, 19u32, 20u32, 21u32, 22u32, 23u32,
+    24u32, 25u32, 26u32, 27u32, 28u32, 29u32, 30u32, 31u32,
+};
+
+fn main() {}
@@ -0,0 +1,50 @@
+#![feature(slicing_syntax)]
+
+// Checks that the programs in `tests/compile-fail` are rejected by `phf_mac`
+// with the errors marked by their `//~ ERROR` comments.
+use std::io::fs;
+use std::io::process::Command;
+use std::io::TempDir;
+use std::os;
+
+// Returns the line number and message of each expected error in `source`.
+fn expected_errors(source: &str) -> Vec<(uint, String)> {
+    source.lines().enumerate().filter_map(|(i, line)| {
+        line.find_str("//~ ERROR ").map(|idx| {
+            (i + 1, line[idx + "//~ ERROR ".len()..].trim().to_string())
+        })
+    }).collect()
+}
+
+#[test]
+fn compile_fail() {
+    // Test binaries are built next to the `phf` rlib, with `phf_mac` in `deps`.
+    let lib_dir = os::self_exe_path().unwrap();
+    let dir = Path::new(file!()).dir_path().join("compile-fail");
+    let out_dir = TempDir::new("phf-compile-fail").unwrap();
+
+    for path in fs::readdir(&dir).unwrap().into_iter() {
+        let source = fs::File::open(&path).read_to_string().unwrap();
+        let expected = expected_errors(source[]);
+        assert!(!expected.is_empty(), "{} has no expected errors", path.display());
+
+        let output = Command::new("rustc")
+                             .arg(&path)
+                             .arg("--out-dir").arg(out_dir.path())
+                             .arg("-L").arg(&lib_dir)
+                             .arg("-L").arg(lib_dir.join("deps"))
+                             .output()
+                             .unwrap();
+        assert!(!output.status.success(), "{} compiled successfully", path.display());
+
+        let stderr = String::from_utf8(output.error).unwrap();
+        for &(line, ref msg) in expected.iter() {
+            let prefix = format!("{}:{}:", path.display(), line);
+            let found = stderr[].lines().any(|l| {
+                l.starts_with(prefix[]) && l.contains(format!("error: {}", msg)[])
+            });
+            assert!(found, "{}:{}: expected error `{}`, got:\n{}", path.display(), line, msg,
+                    stderr);
+        }
+    }
+}
@@ -1,12 +1,16 @@
-#![feature(phase, macro_rules)]
+#![feature(phase, macro_rules, slicing_syntax)]
 
 #[phase(plugin)]
 extern crate phf_mac;
 extern crate phf;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 mod map {
     use std::collections::{HashMap, HashSet};
+    use std::hash;
     use phf;
+    use phf::{NoCase, FoldCase};
 
     #[allow(dead_code)]
     static TRAILING_COMMA: phf::Map<&'static str, int> = phf_map!(
@@ -99,6 +103,50 @@ mod map {
         assert!(MAP.get(&("a")) == Some(&0));
     }
 
+    #[test]
+    fn test_params() {
+        static MAP: phf::Map<&'static str, int> = phf_map!(
+            #[lambda = 1, load_factor = 0.5, max_attempts = 10]
+            "a" => 0,
+            "b" => 1,
+            "c" => 2,
+        );
+        assert_eq!(Some(&0), MAP.get(&"a"));
+        assert_eq!(Some(&1), MAP.get(&"b"));
+        assert_eq!(Some(&2), MAP.get(&"c"));
+        assert_eq!(None, MAP.get(&"d"));
+        assert_eq!(3, MAP.len());
+        assert_eq!(3, MAP.entries().count());
+    }
+
+    #[test]
+    fn test_hashers() {
+        static SIP: phf::Map<&'static str, int, phf::Sip13Hasher> = phf_map!(
+            #[hasher = "sip13"]
+            "foo" => 10,
+            "bar" => 11,
+        );
+        static FX: phf::Map<&'static str, int, phf::FxHasher> = phf_map!(
+            #[hasher = "fx"]
+            "foo" => 10,
+            "bar" => 11,
+        );
+        static MUL_SHIFT: phf::Map<u32, int, phf::MulShiftHasher> = phf_map!(
+            #[hasher = "mul_shift"]
+            1u32 => 10,
+            2u32 => 11,
+        );
+        assert_eq!(Some(&10), SIP.get(&"foo"));
+        assert_eq!(Some(&11), SIP.get(&"bar"));
+        assert_eq!(None, SIP.get(&"baz"));
+        assert_eq!(Some(&10), FX.get(&"foo"));
+        assert_eq!(Some(&11), FX.get(&"bar"));
+        assert_eq!(None, FX.get(&"baz"));
+        assert_eq!(Some(&10), MUL_SHIFT.get(&1));
+        assert_eq!(Some(&11), MUL_SHIFT.get(&2));
+        assert_eq!(None, MUL_SHIFT.get(&3));
+    }
+
     #[test]
     fn test_macro_key() {
         static MAP: phf::Map<&'static str, int> = phf_map!(
@@ -115,6 +163,25 @@ mod map {
         assert_eq!(Some(&0), MAP.get_equiv("a".to_string()[]));
     }
 
+    #[test]
+    fn test_eq() {
+        static MAP: phf::Map<&'static str, int> = phf_map!(
+            "foo" => 10,
+            "bar" => 11,
+        );
+        static REVERSED: phf::Map<&'static str, int> = phf_map!(
+            "bar" => 11,
+            "foo" => 10,
+        );
+        static DIFFERENT: phf::Map<&'static str, int> = phf_map!(
+            "foo" => 10,
+            "bar" => 12,
+        );
+        assert!(MAP == REVERSED);
+        assert!(MAP != DIFFERENT);
+        assert_eq!(hash::hash(&MAP), hash::hash(&REVERSED));
+    }
+
     #[test]
     fn test_index_ok() {
         static MAP: phf::Map<&'static str, int> = phf_map!(
@@ -198,15 +265,158 @@ mod map {
         test_key_type!(u64, 0u64 => 0, 1u64 => 1);
     }
 
+    #[test]
+    fn test_uint_keys() {
+        test_key_type!(uint, 0u => 0, 1u => 1);
+    }
+
+    #[test]
+    fn test_int_keys() {
+        test_key_type!(int, 0i => 0, 1i => 1);
+    }
+
     #[test]
     fn test_bool_keys() {
         test_key_type!(bool, false => 0, true => 1);
     }
+
+    #[test]
+    fn test_nocase_keys() {
+        static MAP: phf::Map<NoCase<&'static str>, int> = phf_map! {
+            NoCase("Content-Type") => 0,
+            NoCase("content-length") => 1,
+        };
+        assert_eq!(Some(&0), MAP.get(&NoCase("content-type")));
+        assert_eq!(Some(&0), MAP.get(&NoCase("CONTENT-TYPE")));
+        assert_eq!(Some(&1), MAP.get(&NoCase("Content-Length")));
+        let header = String::from_str("cOnTeNt-TyPe");
+        assert_eq!(Some(&0), MAP.get_equiv(&NoCase(header[])));
+        assert_eq!(None, MAP.get(&NoCase("content-typ")));
+    }
+
+    #[test]
+    fn test_foldcase_keys() {
+        static MAP: phf::Map<FoldCase<&'static str>, int> = phf_map! {
+            FoldCase("Σίσυφος") => 0,
+            FoldCase("straße") => 1,
+            FoldCase("Kelvin") => 2,
+        };
+        assert_eq!(Some(&0), MAP.get(&FoldCase("ΣΊΣΥΦΟΣ")));
+        assert_eq!(Some(&0), MAP.get(&FoldCase("σίσυφοσ")));
+        assert_eq!(Some(&1), MAP.get(&FoldCase("STRAßE")));
+        assert_eq!(Some(&1), MAP.get(&FoldCase("STRASSE")));
+        assert_eq!(Some(&1), MAP.get(&FoldCase("strasse")));
+        assert_eq!(Some(&1), MAP.get(&FoldCase("STRA\u1E9EE")));
+        assert_eq!(Some(&2), MAP.get(&FoldCase("\u212Aelvin")));
+        let word = String::from_str("KELVIN");
+        assert_eq!(Some(&2), MAP.get_equiv(&FoldCase(word[])));
+        assert_eq!(None, MAP.get(&FoldCase("strase")));
+    }
+
+    #[phf_enum]
+    #[deriving(PartialEq, Eq)]
+    enum Color {
+        Red,
+        Green,
+        Blue = 10,
+        Cyan,
+        Black = -1,
+    }
+
+    #[test]
+    fn test_enum_keys() {
+        test_key_type!(Color, Color::Red => 0, Color::Green => 1, Color::Blue => 2,
+                       Color::Cyan => 3, Color::Black => 4);
+    }
+
+    #[test]
+    fn test_from_tsv() {
+        static MAP: phf::Map<u16, &'static str> =
+            phf_map_from_file!("codes.tsv", u16, &'static str);
+        assert_eq!(3, MAP.len());
+        assert_eq!(Some(&"OK"), MAP.get(&200));
+        assert_eq!(Some(&"Not Found"), MAP.get(&404));
+        assert_eq!(Some(&"Internal Server Error"), MAP.get(&500));
+        assert_eq!(None, MAP.get(&418));
+    }
+
+    #[deriving(PartialEq, Show)]
+    enum Keyword {
+        Loop,
+        Continue,
+        Break,
+        Op(int, int),
+    }
+
+    #[test]
+    fn test_from_csv() {
+        static MAP: phf::Map<&'static str, Keyword> =
+            phf_map_from_file!("keywords.csv", &'static str, Keyword);
+        assert_eq!(4, MAP.len());
+        assert_eq!(Some(&Keyword::Loop), MAP.get(&"loop"));
+        assert_eq!(Some(&Keyword::Continue), MAP.get(&"continue"));
+        assert_eq!(Some(&Keyword::Break), MAP.get(&"break"));
+        assert_eq!(Some(&Keyword::Op(1, 2)), MAP.get(&"op"));
+    }
+
+    #[test]
+    fn test_from_json() {
+        static MAP: phf::Map<&'static str, &'static str> =
+            phf_map_from_file!("greetings.json", &'static str, &'static str);
+        assert_eq!(3, MAP.len());
+        assert_eq!(Some(&"Hello"), MAP.get(&"en"));
+        assert_eq!(Some(&"Bonjour"), MAP.get(&"fr"));
+        assert_eq!(Some(&"Hallo"), MAP.get(&"de"));
+    }
+
+    #[test]
+    fn test_from_toml() {
+        static MAP: phf::Map<&'static str, u16> =
+            phf_map_from_file!("ports.toml", &'static str, u16);
+        assert_eq!(3, MAP.len());
+        assert_eq!(Some(&22), MAP.get(&"ssh"));
+        assert_eq!(Some(&80), MAP.get(&"http"));
+        assert_eq!(Some(&443), MAP.get(&"https"));
+    }
+
+    #[test]
+    fn test_tuple_keys() {
+        test_key_type!((u8, u8), (0u8, 1u8) => 0, (1u8, 0u8) => 1, (1u8, 1u8) => 2);
+    }
+
+    #[test]
+    fn test_mixed_tuple_keys() {
+        test_key_type!((&'static str, char, bool), ("a", 'b', true) => 0, ("a", 'b', false) => 1);
+    }
+
+    #[test]
+    fn test_binary_tuple_keys() {
+        test_key_type!((&'static [u8], &'static [u8]),
+                       (b"ab", b"c") => 0,
+                       (b"a", b"bc") => 1,
+                       (b"a\xff", b"") => 2,
+                       (b"a", b"\xff") => 3);
+    }
+
+    #[test]
+    fn test_array_keys() {
+        test_key_type!([u8, ..4], [0x7fu8, b'E', b'L', b'F'] => 0, [b'M', b'Z', 0u8, 0u8] => 1);
+    }
+
+    #[test]
+    fn test_nested_keys() {
+        test_key_type!(([u16, ..2], (i8,)),
+                       ([1u16, 2u16], (3i8,)) => 0,
+                       ([2u16, 1u16], (3i8,)) => 1);
+    }
 }
 
 mod set {
-    use std::collections::HashSet;
+    use std::collections::{HashSet, BTreeSet};
+    use std::collections::hash_set::SetItems;
+    use std::hash;
     use phf;
+    use phf::{SetLike, SetValues};
 
     #[allow(dead_code)]
     static TRAILING_COMMA: phf::Set<&'static str> = phf_set! {
@@ -250,9 +460,85 @@ mod set {
         };
         assert!(SET.contains_equiv("hello".to_string()[]));
     }
+
+    static LEFT: phf::Set<u32> = phf_set! { 1u32, 2u32, 3u32 };
+    static RIGHT: phf::Set<u32> = phf_set! { 2u32, 3u32, 4u32 };
+
+    struct Wrapper(HashSet<u32>);
+
+    impl SetLike<u32> for Wrapper {
+        fn contains(&self, value: &u32) -> bool {
+            self.0.contains(value)
+        }
+    }
+
+    impl<'a> SetValues<'a, u32, SetItems<'a, u32>> for Wrapper {
+        fn values(&'a self) -> SetItems<'a, u32> {
+            self.0.iter()
+        }
+    }
+
+    fn sorted<'a, I>(iter: I) -> Vec<u32> where I: Iterator<&'a u32> {
+        let mut vec = iter.map(|&v| v).collect::<Vec<_>>();
+        vec.sort();
+        vec
+    }
+
+    #[test]
+    fn test_union() {
+        assert_eq!(vec![1, 2, 3, 4], sorted(LEFT.union(&RIGHT)));
+    }
+
+    #[test]
+    fn test_intersection() {
+        assert_eq!(vec![2, 3], sorted(LEFT.intersection(&RIGHT)));
+    }
+
+    #[test]
+    fn test_difference() {
+        assert_eq!(vec![1], sorted(LEFT.difference(&RIGHT)));
+        assert_eq!(vec![4], sorted(RIGHT.difference(&LEFT)));
+    }
+
+    #[test]
+    fn test_symmetric_difference() {
+        assert_eq!(vec![1, 4], sorted(LEFT.symmetric_difference(&RIGHT)));
+    }
+
+    #[test]
+    fn test_set_like() {
+        let other = Wrapper(vec![3u32, 5].into_iter().collect());
+        assert_eq!(vec![1, 2, 3, 5], sorted(LEFT.union(&other)));
+        assert_eq!(vec![3], sorted(LEFT.intersection(&other)));
+        assert_eq!(vec![1, 2, 5], sorted(LEFT.symmetric_difference(&other)));
+        assert!(!LEFT.is_disjoint(&other));
+        assert!(!LEFT.is_superset(&other));
+        assert!(LEFT.is_superset(&Wrapper(vec![1u32, 2].into_iter().collect())));
+        assert!(LEFT.is_disjoint(&Wrapper(vec![4u32].into_iter().collect())));
+    }
+
+    #[test]
+    fn test_btree_set() {
+        let other = vec![3u32, 5].into_iter().collect::<BTreeSet<_>>();
+        assert_eq!(vec![1, 2, 3, 5], sorted(LEFT.union(&other)));
+        assert_eq!(vec![3], sorted(LEFT.intersection(&other)));
+        assert_eq!(vec![1, 2], sorted(LEFT.difference(&other)));
+        assert_eq!(vec![1, 2, 5], sorted(LEFT.symmetric_difference(&other)));
+        assert!(LEFT.is_subset(&vec![1u32, 2, 3, 4].into_iter().collect::<BTreeSet<_>>()));
+        assert!(!LEFT.is_superset(&other));
+    }
+
+    #[test]
+    fn test_eq() {
+        static REVERSED: phf::Set<u32> = phf_set! { 3u32, 2u32, 1u32 };
+        assert!(LEFT == REVERSED);
+        assert!(LEFT != RIGHT);
+        assert_eq!(hash::hash(&LEFT), hash::hash(&REVERSED));
+    }
 }
 
 mod ordered_map {
+    use std::hash;
     use phf;
 
     #[allow(dead_code)]
@@ -351,9 +637,82 @@ mod ordered_map {
         );
         assert_eq!(Some(&0), MAP.get_equiv("a".to_string()[]));
     }
+
+    #[test]
+    fn test_index() {
+        static MAP: phf::OrderedMap<&'static str, int> = phf_ordered_map!(
+            "foo" => 10,
+            "bar" => 11,
+            "baz" => 12,
+        );
+        assert_eq!(Some((&"foo", &10)), MAP.get_at(0));
+        assert_eq!(Some((&"baz", &12)), MAP.get_at(MAP.get_index(&"baz").unwrap()));
+        assert_eq!(None, MAP.get_at(3));
+        assert_eq!(Some((&"foo", &10)), MAP.first());
+        assert_eq!(Some((&"baz", &12)), MAP.last());
+    }
+
+    #[test]
+    fn test_cmp() {
+        static MAP: phf::OrderedMap<&'static str, int> = phf_ordered_map!(
+            "foo" => 10,
+            "bar" => 11,
+        );
+        static SAME: phf::OrderedMap<&'static str, int> = phf_ordered_map!(
+            "foo" => 10,
+            "bar" => 11,
+        );
+        static REVERSED: phf::OrderedMap<&'static str, int> = phf_ordered_map!(
+            "bar" => 11,
+            "foo" => 10,
+        );
+        assert!(MAP == SAME);
+        assert!(MAP != REVERSED);
+        assert_eq!(Greater, MAP.cmp(&REVERSED));
+        assert_eq!(Some(Less), REVERSED.partial_cmp(&MAP));
+        assert_eq!(hash::hash(&MAP), hash::hash(&SAME));
+        assert!(hash::hash(&MAP) != hash::hash(&REVERSED));
+    }
+
+    #[test]
+    fn test_empty_first_last() {
+        static MAP: phf::OrderedMap<&'static str, int> = phf_ordered_map!();
+        assert_eq!(None, MAP.first());
+        assert_eq!(None, MAP.last());
+        assert_eq!(None, MAP.get(&"a"));
+        assert_eq!(None, MAP.get_index(&"a"));
+    }
+
+    #[test]
+    fn test_ranges() {
+        static MAP: phf::OrderedMap<&'static str, int> = phf_ordered_map!(
+            "foo" => 10,
+            "bar" => 11,
+            "baz" => 12,
+        );
+        let vec = MAP.entries_range(1, 3).unwrap().map(|&(k, v)| (k, v)).collect::<Vec<_>>();
+        assert_eq!(vec, vec!(("bar", 11i), ("baz", 12)));
+        assert_eq!(0, MAP.entries_range(1, 1).unwrap().count());
+        let vec = MAP.keys_from(1).unwrap().map(|&k| k).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("bar", "baz"));
+        let vec = MAP.values_from(2).unwrap().map(|&v| v).collect::<Vec<_>>();
+        assert_eq!(vec, vec!(12i));
+        assert_eq!(0, MAP.values_from(3).unwrap().count());
+    }
+
+    #[test]
+    fn test_invalid_ranges() {
+        static MAP: phf::OrderedMap<&'static str, int> = phf_ordered_map!(
+            "foo" => 10,
+        );
+        assert!(MAP.entries_range(0, 2).is_none());
+        assert!(MAP.keys_range(1, 0).is_none());
+        assert!(MAP.values_from(2).is_none());
+    }
 }
 
 mod ordered_set {
+    use std::hash;
     use phf;
 
     #[allow(dead_code)]
@@ -408,6 +767,20 @@ mod ordered_set {
         assert_eq!(vec, vec!("hello", "there", "world"));
     }
 
+    #[test]
+    fn test_params() {
+        static SET: phf::OrderedSet<&'static str> = phf_ordered_set! {
+            #[load_factor = 0.75]
+            "hello",
+            "there",
+            "world",
+        };
+        assert!(SET.contains(&"hello"));
+        assert!(!SET.contains(&"foo"));
+        let vec = SET.iter().map(|&e| e).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("hello", "there", "world"));
+    }
+
     #[test]
     fn test_non_static_str_contains() {
         static SET: phf::OrderedSet<&'static str> = phf_ordered_set! {
@@ -416,4 +789,628 @@ mod ordered_set {
         };
         assert!(SET.contains_equiv("hello".to_string()[]));
     }
+
+    #[test]
+    fn test_index() {
+        static SET: phf::OrderedSet<&'static str> = phf_ordered_set! {
+            "foo",
+            "bar",
+            "baz",
+        };
+        assert_eq!(Some(&"bar"), SET.get_at(1));
+        assert_eq!(None, SET.get_at(3));
+        assert_eq!(Some(&"foo"), SET.first());
+        assert_eq!(Some(&"baz"), SET.last());
+        let vec = SET.iter_range(0, 2).unwrap().map(|&e| e).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("foo", "bar"));
+        let vec = SET.iter_from(1).unwrap().map(|&e| e).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("bar", "baz"));
+        assert!(SET.iter_from(4).is_none());
+    }
+
+    static LEFT: phf::OrderedSet<&'static str> = phf_ordered_set! { "d", "c", "b", "a" };
+    static RIGHT: phf::OrderedSet<&'static str> = phf_ordered_set! { "e", "a", "c" };
+
+    #[test]
+    fn test_set_ops() {
+        let vec = LEFT.union(&RIGHT).map(|&e| e).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("d", "c", "b", "a", "e"));
+        let vec = LEFT.intersection(&RIGHT).map(|&e| e).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("c", "a"));
+        let vec = LEFT.difference(&RIGHT).map(|&e| e).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("d", "b"));
+        let vec = LEFT.symmetric_difference(&RIGHT).map(|&e| e).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("d", "b", "e"));
+    }
+
+    #[test]
+    fn test_mixed_set_ops() {
+        static OTHER: phf::Set<&'static str> = phf_set! { "a", "b" };
+        let vec = LEFT.intersection(&OTHER).map(|&e| e).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("b", "a"));
+        assert!(LEFT.is_superset(&OTHER));
+        assert!(OTHER.is_subset(&LEFT));
+        assert!(!RIGHT.is_superset(&OTHER));
+    }
+
+    #[test]
+    fn test_cmp() {
+        static SAME: phf::OrderedSet<&'static str> = phf_ordered_set! { "d", "c", "b", "a" };
+        assert!(LEFT == SAME);
+        assert!(LEFT != RIGHT);
+        assert_eq!(Less, LEFT.cmp(&RIGHT));
+        assert_eq!(hash::hash(&LEFT), hash::hash(&SAME));
+    }
+}
+
+mod bimap {
+    use phf;
+
+    static STATUSES: phf::BiMap<&'static str, u16> = phf_bimap! {
+        "OK" => 200u16,
+        "Moved Permanently" => 301u16,
+        "Not Found" => 404u16,
+    };
+
+    #[test]
+    fn test_get() {
+        assert_eq!(Some(&200), STATUSES.get_by_left("OK"));
+        assert_eq!(Some(&404), STATUSES.get_by_left(&"Not Found"));
+        assert_eq!(None, STATUSES.get_by_left("Teapot"));
+        assert_eq!(Some(&"Moved Permanently"), STATUSES.get_by_right(&301u16));
+        assert_eq!(None, STATUSES.get_by_right(&418u16));
+        assert_eq!(3, STATUSES.len());
+    }
+
+    #[test]
+    fn test_contains() {
+        assert!(STATUSES.contains_left("OK"));
+        assert!(!STATUSES.contains_left("ok"));
+        assert!(STATUSES.contains_right(&404u16));
+        assert!(!STATUSES.contains_right(&500u16));
+    }
+
+    #[test]
+    fn test_empty() {
+        static EMPTY: phf::BiMap<&'static str, u16> = phf_bimap! {};
+        assert!(EMPTY.is_empty());
+        assert_eq!(None, EMPTY.get_by_left("OK"));
+        assert_eq!(None, EMPTY.get_by_right(&200u16));
+        assert!(!EMPTY.contains_right(&200u16));
+    }
+
+    #[test]
+    fn test_entries() {
+        let vec = STATUSES.entries().map(|&(l, r)| (l, r)).collect::<Vec<_>>();
+        assert_eq!(vec, vec!(("OK", 200u16), ("Moved Permanently", 301), ("Not Found", 404)));
+    }
+}
+
+mod multimap {
+    use phf;
+
+    static MIME_TYPES: phf::MultiMap<&'static str, &'static str> = phf_multimap! {
+        "html" => "text/html",
+        "png" => "image/png",
+        "html" => "application/xhtml+xml",
+        "js" => "application/javascript",
+        "js" => "text/javascript",
+    };
+
+    #[test]
+    fn test_get_all() {
+        assert_eq!(["text/html", "application/xhtml+xml"][], MIME_TYPES.get_all("html"));
+        assert_eq!(["image/png"][], MIME_TYPES.get_all(&"png"));
+        assert!(MIME_TYPES.get_all("gif").is_empty());
+        assert_eq!(Some(&"application/javascript"), MIME_TYPES.get("js"));
+        assert!(MIME_TYPES.contains_key("png"));
+        assert!(!MIME_TYPES.contains_key("gif"));
+        assert_eq!(3, MIME_TYPES.len());
+        assert_eq!(5, MIME_TYPES.num_values());
+    }
+
+    #[test]
+    fn test_entries() {
+        let vec = MIME_TYPES.entries().map(|(&k, vs)| (k, vs.len())).collect::<Vec<_>>();
+        assert_eq!(vec, vec!(("html", 2u), ("png", 1), ("js", 2)));
+        let vec = MIME_TYPES.keys().map(|&k| k).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("html", "png", "js"));
+    }
+}
+
+mod prefix_map {
+    use phf;
+
+    static ROUTES: phf::PrefixMap<int> = phf_prefix_map! {
+        "/" => 0,
+        "/api" => 1,
+        "/api/v2" => 2,
+        "/api/v2/users" => 3,
+        "/static" => 4,
+    };
+
+    #[test]
+    fn test_get() {
+        assert_eq!(Some(&1), ROUTES.get("/api"));
+        assert_eq!(None, ROUTES.get("/api/v"));
+        assert!(ROUTES.contains_key("/static"));
+        assert_eq!(5, ROUTES.len());
+    }
+
+    #[test]
+    fn test_empty() {
+        static EMPTY: phf::PrefixMap<int> = phf_prefix_map!();
+        assert!(EMPTY.is_empty());
+        assert_eq!(None, EMPTY.get("/"));
+        assert!(!EMPTY.contains_key("/"));
+        assert_eq!(None, EMPTY.longest_prefix("/api"));
+    }
+
+    #[test]
+    fn test_longest_prefix() {
+        assert_eq!(Some(("/api/v2/users", &3)), ROUTES.longest_prefix("/api/v2/users/7"));
+        assert_eq!(Some(("/api/v2", &2)), ROUTES.longest_prefix("/api/v2"));
+        assert_eq!(Some(("/api", &1)), ROUTES.longest_prefix("/api/v1/users"));
+        assert_eq!(Some(("/", &0)), ROUTES.longest_prefix("/index.html"));
+        assert_eq!(None, ROUTES.longest_prefix("api"));
+    }
+
+    #[test]
+    fn test_prefixes_of() {
+        let vec = ROUTES.prefixes_of("/api/v2/users/7").map(|(k, &v)| (k, v)).collect::<Vec<_>>();
+        assert_eq!(vec, vec!(("/", 0i), ("/api", 1), ("/api/v2", 2), ("/api/v2/users", 3)));
+        assert_eq!(0, ROUTES.prefixes_of("").count());
+    }
+
+    #[test]
+    fn test_char_boundaries() {
+        static MAP: phf::PrefixMap<int> = phf_prefix_map! {
+            "a" => 0,
+            "aé" => 1,
+            "ab" => 2,
+        };
+        assert_eq!(Some(("aé", &1)), MAP.longest_prefix("aéb"));
+        let vec = MAP.prefixes_of("aéb").map(|(k, &v)| (k, v)).collect::<Vec<_>>();
+        assert_eq!(vec, vec!(("a", 0i), ("aé", 1)));
+    }
+}
+
+mod sorted_map {
+    use phf;
+
+    static MAP: phf::SortedMap<&'static str, int> = phf_sorted_map! {
+        "delta" => 3,
+        "alpha" => 0,
+        "charlie" => 2,
+        "bravo" => 1,
+    };
+
+    static LIMITS: phf::SortedMap<i32, &'static str> = phf_sorted_map! {
+        100i32 => "large",
+        0i32 => "zero",
+        5i32 => "tiny",
+        10i32 => "small",
+    };
+
+    #[test]
+    fn test_get() {
+        assert_eq!(Some(&2), MAP.get("charlie"));
+        assert_eq!(None, MAP.get("echo"));
+        assert_eq!(Some(1), MAP.get_index("bravo"));
+        assert_eq!(4, MAP.len());
+    }
+
+    #[test]
+    fn test_empty() {
+        static EMPTY: phf::SortedMap<&'static str, int> = phf_sorted_map!();
+        assert!(EMPTY.is_empty());
+        assert_eq!(None, EMPTY.get("alpha"));
+        assert_eq!(None, EMPTY.get_index("alpha"));
+        assert!(!EMPTY.contains_key("alpha"));
+        assert_eq!(0, EMPTY.range("a", "z").count());
+        assert_eq!(None, EMPTY.floor("alpha"));
+        assert_eq!(None, EMPTY.ceiling("alpha"));
+        assert_eq!(None, EMPTY.min());
+    }
+
+    #[test]
+    fn test_sorted() {
+        let vec = MAP.keys().map(|&k| k).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("alpha", "bravo", "charlie", "delta"));
+        let vec = LIMITS.keys().map(|&k| k).collect::<Vec<_>>();
+        assert_eq!(vec, vec!(0i32, 5, 10, 100));
+    }
+
+    #[test]
+    fn test_range() {
+        let vec = MAP.range("b", "d").map(|&(k, _)| k).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("bravo", "charlie"));
+        let vec = LIMITS.range(&0i32, &100i32).map(|&(_, v)| v).collect::<Vec<_>>();
+        assert_eq!(vec, vec!("zero", "tiny", "small"));
+        assert_eq!(0, LIMITS.range(&100i32, &0i32).count());
+    }
+
+    #[test]
+    fn test_floor_ceiling() {
+        assert_eq!(Some((&10, &"small")), LIMITS.floor(&50i32));
+        assert_eq!(Some((&10, &"small")), LIMITS.floor(&10i32));
+        assert_eq!(None, LIMITS.floor(&-1i32));
+        assert_eq!(Some((&100, &"large")), LIMITS.ceiling(&50i32));
+        assert_eq!(None, LIMITS.ceiling(&101i32));
+    }
+
+    #[test]
+    fn test_min_max() {
+        assert_eq!(Some((&0, &"zero")), LIMITS.min());
+        assert_eq!(Some((&100, &"large")), LIMITS.max());
+        assert_eq!(Some((&"alpha", &0)), MAP.min());
+        assert_eq!(Some((&"delta", &3)), MAP.max());
+    }
+}
+
+mod range_map {
+    use phf;
+
+    #[deriving(PartialEq, Show)]
+    enum Block {
+        Latin,
+        Latin1,
+        Greek,
+    }
+
+    static BLOCKS: phf::RangeMap<u32, Block> = phf_range_map! {
+        0x0370u32...0x03FFu32 => Block::Greek,
+        0x0000u32..0x0080u32 => Block::Latin,
+        0x0080u32...0x00FFu32 => Block::Latin1,
+    };
+
+    #[test]
+    fn test_get() {
+        assert_eq!(Some(&Block::Latin), BLOCKS.get(&('a' as u32)));
+        assert_eq!(Some(&Block::Latin), BLOCKS.get(&0x7F));
+        assert_eq!(Some(&Block::Latin1), BLOCKS.get(&0x80));
+        assert_eq!(Some(&Block::Latin1), BLOCKS.get(&0xFF));
+        assert_eq!(None, BLOCKS.get(&0x100));
+        assert_eq!(Some(&Block::Greek), BLOCKS.get(&('λ' as u32)));
+        assert_eq!(None, BLOCKS.get(&0x400));
+        assert!(BLOCKS.contains(&0x370));
+        assert_eq!(Some((&0x00, &0x7F, &Block::Latin)), BLOCKS.get_range(&0x41));
+    }
+
+    #[test]
+    fn test_entries() {
+        let vec = BLOCKS.entries().map(|&(start, end, _)| (start, end)).collect::<Vec<_>>();
+        assert_eq!(vec, vec!((0x00u32, 0x7F), (0x80, 0xFF), (0x370, 0x3FF)));
+        assert_eq!(3, BLOCKS.len());
+    }
+
+    #[test]
+    fn test_signed() {
+        static SIGNS: phf::RangeMap<i32, &'static str> = phf_range_map! {
+            -100i32..0i32 => "negative",
+            0i32...0i32 => "zero",
+            1i32...100i32 => "positive",
+        };
+        assert_eq!(Some(&"negative"), SIGNS.get(&-100));
+        assert_eq!(Some(&"negative"), SIGNS.get(&-1));
+        assert_eq!(Some(&"zero"), SIGNS.get(&0));
+        assert_eq!(Some(&"positive"), SIGNS.get(&100));
+        assert_eq!(None, SIGNS.get(&-101));
+    }
+}
+
+mod dynamic_map {
+    use std::collections::HashMap;
+    use std::hash::Writer;
+    use phf::{DynamicMap, PhfHash, Sip13Hasher, NoCase, FoldCase};
+
+    #[deriving(PartialEq, Eq)]
+    struct Colliding(u32);
+
+    impl PhfHash for Colliding {
+        fn phf_hash_into<W>(&self, _: &mut W) where W: Writer {}
+    }
+
+    #[test]
+    fn test_two() {
+        let map = DynamicMap::from_entries(vec![("foo", 10i), ("bar", 11)]).unwrap();
+        assert!(Some(&10) == map.get(&"foo"));
+        assert!(Some(&11) == map.get(&"bar"));
+        assert_eq!(None, map.get(&"asdf"));
+        assert_eq!(2, map.len());
+    }
+
+    #[test]
+    fn test_entries() {
+        let map = DynamicMap::from_entries(vec![("foo", 10i), ("bar", 11)]).unwrap();
+        let hash = map.entries().map(|&e| e).collect::<HashMap<_, int>>();
+        assert!(Some(&10) == hash.get(&("foo")));
+        assert!(Some(&11) == hash.get(&("bar")));
+        assert_eq!(2, hash.len());
+    }
+
+    #[test]
+    fn test_large() {
+        let entries = range(0u32, 1000).map(|i| (i, i * 2)).collect::<Vec<_>>();
+        let map = DynamicMap::from_entries(entries).unwrap();
+        for i in range(0u32, 1000) {
+            assert_eq!(Some(&(i * 2)), map.get(&i));
+        }
+        assert_eq!(None, map.get(&1000));
+    }
+
+    #[test]
+    fn test_empty() {
+        let map: DynamicMap<u32, u32> = DynamicMap::from_entries(vec![]).unwrap();
+        assert!(map.is_empty());
+        assert_eq!(None, map.get(&0));
+    }
+
+    #[test]
+    fn test_duplicates() {
+        assert!(DynamicMap::from_entries(vec![("foo", 10i), ("foo", 11)]).is_none());
+    }
+
+    #[test]
+    fn test_nocase() {
+        let entries = vec![(NoCase(String::from_str("Accept")), 0i),
+                           (NoCase(String::from_str("Host")), 1)];
+        let map = DynamicMap::from_entries(entries).unwrap();
+        assert_eq!(Some(&0), map.get_equiv(&NoCase("accept")));
+        assert_eq!(Some(&1), map.get_equiv(&NoCase("HOST")));
+
+        let entries = vec![(NoCase("Accept"), 0i), (NoCase("ACCEPT"), 1)];
+        assert!(DynamicMap::from_entries(entries).is_none());
+    }
+
+    #[test]
+    fn test_foldcase() {
+        let entries = vec![(FoldCase("Ünïcödé"), 0i), (FoldCase("ÜNÏCÖDÉ"), 1)];
+        assert!(DynamicMap::from_entries(entries).is_none());
+    }
+
+    #[test]
+    fn test_hasher() {
+        let entries = range(0u32, 100).map(|i| (i, i * 2)).collect::<Vec<_>>();
+        let map = DynamicMap::from_entries_with_hasher(entries, Sip13Hasher).unwrap();
+        for i in range(0u32, 100) {
+            assert_eq!(Some(&(i * 2)), map.get(&i));
+        }
+        assert_eq!(None, map.get(&100));
+    }
+
+    #[test]
+    fn test_colliding_hashes() {
+        let entries = vec![(Colliding(0), 0i), (Colliding(1), 1)];
+        assert!(DynamicMap::from_entries(entries).is_none());
+    }
+
+    #[test]
+    fn test_non_static_str_key() {
+        let map = DynamicMap::from_entries(vec![("a", 0i)]).unwrap();
+        assert_eq!(Some(&0), map.get_equiv("a".to_string()[]));
+    }
+}
+
+mod mapped_map {
+    use phf::mapped_map::{mod, MappedMap, MappedMapError, SerializeError};
+
+    #[test]
+    fn test_roundtrip() {
+        let entries = range(0u, 1000).map(|i| (i.to_string(), (i * 2).to_string()))
+                                     .collect::<Vec<_>>();
+        let pairs = entries.iter().map(|&(ref k, ref v)| (k.as_bytes(), v.as_bytes()))
+                                  .collect::<Vec<_>>();
+        let data = mapped_map::serialize(pairs[]).unwrap();
+        let map = MappedMap::new(data[]).unwrap();
+        assert_eq!(1000, map.len());
+        for &(ref k, ref v) in entries.iter() {
+            assert_eq!(Some(v.as_bytes()), map.get(k.as_bytes()));
+        }
+        assert_eq!(None, map.get(b"1000"));
+        assert_eq!(1000, map.entries().count());
+    }
+
+    #[test]
+    fn test_empty() {
+        let data = mapped_map::serialize(&[]).unwrap();
+        let map = MappedMap::new(data[]).unwrap();
+        assert!(map.is_empty());
+        assert_eq!(None, map.get(b"foo"));
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let entries = [(b"foo", b"1"), (b"bar", b"2"), (b"foo", b"3"), (b"bar", b"4")];
+        assert_eq!(Some(SerializeError::DuplicateKey(2)), mapped_map::serialize(&entries).err());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let data = mapped_map::serialize(&[(b"foo", b"1"), (b"bar", b"2")]).unwrap();
+        assert_eq!(Some(MappedMapError::InvalidLength), MappedMap::new(data[..10]).err());
+        let truncated = data[..data.len() - 1];
+        assert_eq!(Some(MappedMapError::InvalidLength), MappedMap::new(truncated).err());
+
+        let mut bad = data.clone();
+        bad[0] = b'X';
+        assert_eq!(Some(MappedMapError::InvalidMagic), MappedMap::new(bad[]).err());
+
+        let mut bad = data.clone();
+        bad[4] = 2;
+        assert_eq!(Some(MappedMapError::UnsupportedVersion(2)), MappedMap::new(bad[]).err());
+
+        // Point the first entry's key past the end of the blob.
+        let mut bad = data.clone();
+        let entries_start = data.len() - 8 - 2 * 16;
+        bad[entries_start + 4] = 100;
+        assert_eq!(Some(MappedMapError::InvalidEntry(0)), MappedMap::new(bad[]).err());
+    }
+}
+
+mod borrow {
+    use phf;
+    use phf::{DynamicMap, PhfHash, PhfHasher, PhfBorrow, XxHasher, NoCase, FoldCase};
+
+    fn check_hash<Sized? B, K>(owned: K) where K: PhfHash+PhfBorrow<B>, B: PhfHash {
+        let borrowed: &B = owned.borrow();
+        assert_eq!(XxHasher.hash(&owned, 0), XxHasher.hash(borrowed, 0));
+    }
+
+    #[test]
+    fn test_hashes_match() {
+        check_hash::<str, _>("foo");
+        check_hash::<&'static str, _>("foo");
+        check_hash::<[u8], _>(b"foo");
+        check_hash::<str, _>(String::from_str("foo"));
+        check_hash::<[u8], _>(b"foo".to_vec());
+        check_hash::<u8, _>(1u8);
+        check_hash::<i8, _>(-1i8);
+        check_hash::<u16, _>(1u16);
+        check_hash::<i16, _>(-1i16);
+        check_hash::<u32, _>(1u32);
+        check_hash::<i32, _>(-1i32);
+        check_hash::<u64, _>(1u64);
+        check_hash::<i64, _>(-1i64);
+        check_hash::<uint, _>(1u);
+        check_hash::<int, _>(-1i);
+        check_hash::<char, _>('a');
+        check_hash::<bool, _>(true);
+        check_hash::<(u8, char), _>((1u8, 'a'));
+        check_hash::<[u16, ..3], _>([1u16, 2, 3]);
+        check_hash::<NoCase<&'static str>, _>(NoCase("Foo"));
+        check_hash::<FoldCase<&'static str>, _>(FoldCase("Foo"));
+    }
+
+    #[test]
+    fn test_str() {
+        static MAP: phf::Map<&'static str, int> = phf_map! {
+            "foo" => 0,
+            "bar" => 1,
+        };
+        let key = String::from_str("foo");
+        assert_eq!(Some(&0), MAP.get(key[]));
+        assert_eq!(Some(&1), MAP.get("bar"));
+        assert_eq!(Some(&1), MAP.get(&"bar"));
+        assert!(MAP.contains_key("foo"));
+        assert_eq!(Some(&"bar"), MAP.get_key("bar"));
+        assert_eq!(None, MAP.get("baz"));
+    }
+
+    #[test]
+    fn test_binary() {
+        static SET: phf::Set<&'static [u8]> = phf_set! {
+            b"foo",
+            b"bar",
+        };
+        let key = b"foo".to_vec();
+        assert!(SET.contains(key[]));
+        assert!(SET.contains(b"bar"));
+        assert!(!SET.contains(b"baz"));
+    }
+
+    #[test]
+    fn test_ordered() {
+        static MAP: phf::OrderedMap<&'static str, int> = phf_ordered_map! {
+            "foo" => 0,
+            "bar" => 1,
+        };
+        static SET: phf::OrderedSet<&'static str> = phf_ordered_set! {
+            "foo",
+            "bar",
+        };
+        let key = String::from_str("bar");
+        assert_eq!(Some(&1), MAP.get(key[]));
+        assert_eq!(Some(1), MAP.get_index(key[]));
+        assert!(SET.contains(key[]));
+        assert_eq!(Some(1), SET.get_index(key[]));
+    }
+
+    #[test]
+    fn test_dynamic() {
+        let entries = vec![(String::from_str("foo"), 0i), (String::from_str("bar"), 1)];
+        let map = DynamicMap::from_entries(entries).unwrap();
+        assert_eq!(Some(&0), map.get("foo"));
+        assert_eq!(Some(&1), map.get(&String::from_str("bar")));
+        assert_eq!(None, map.get("baz"));
+
+        let entries = vec![(b"foo".to_vec(), 0i)];
+        let map = DynamicMap::from_entries(entries).unwrap();
+        assert_eq!(Some(&0), map.get(b"foo"));
+    }
+}
+
+mod from_str {
+    #[phf_from_str]
+    #[deriving(PartialEq, Show)]
+    enum Keyword {
+        #[phf(name = "loop", alias = "lp")]
+        Loop,
+        #[phf(name = "continue", alias = "cont", alias = "next")]
+        Continue,
+        Break,
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Some(Keyword::Loop), from_str("loop"));
+        assert_eq!(Some(Keyword::Loop), from_str("lp"));
+        assert_eq!(Some(Keyword::Continue), from_str("next"));
+        assert_eq!(Some(Keyword::Break), from_str("Break"));
+        assert_eq!(None::<Keyword>, from_str("break"));
+    }
+
+    #[test]
+    fn test_as_str() {
+        assert_eq!("loop", Keyword::Loop.as_str());
+        assert_eq!("continue", Keyword::Continue.as_str());
+        assert_eq!("Break", Keyword::Break.as_str());
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(6, Keyword::names().len());
+        assert_eq!(Some(&Keyword::Continue), Keyword::names().get("cont"));
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_tests {
+    use serde::json;
+    use phf;
+
+    #[test]
+    fn test_map() {
+        static MAP: phf::Map<&'static str, int> = phf_map!(
+            "foo" => 10,
+        );
+        assert_eq!("{\"foo\":10}", json::to_string(&MAP).unwrap()[]);
+    }
+
+    #[test]
+    fn test_set() {
+        static SET: phf::Set<&'static str> = phf_set! {
+            "foo",
+        };
+        assert_eq!("[\"foo\"]", json::to_string(&SET).unwrap()[]);
+    }
+
+    #[test]
+    fn test_ordered_map() {
+        static MAP: phf::OrderedMap<&'static str, int> = phf_ordered_map!(
+            "foo" => 10,
+            "bar" => 11,
+            "baz" => 12,
+        );
+        assert_eq!("{\"foo\":10,\"bar\":11,\"baz\":12}", json::to_string(&MAP).unwrap()[]);
+    }
+
+    #[test]
+    fn test_ordered_set() {
+        static SET: phf::OrderedSet<&'static str> = phf_ordered_set! {
+            "foo",
+            "bar",
+        };
+        assert_eq!("[\"foo\",\"bar\"]", json::to_string(&SET).unwrap()[]);
+    }
 }
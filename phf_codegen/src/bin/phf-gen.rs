@@ -0,0 +1,523 @@
+//! Generates a Rust module containing a PHF table from a data file.
+//!
+//! `csv` and `tsv` files hold a key and a value on each line, or just a key
+//! when generating a set. A line is split at its first separator, so values
+//! may contain the separator but keys may not. Blank lines and lines starting
+//! with `#` are skipped. `json` files hold an object of keys to values, or an
+//! array of keys. `toml` files hold a table of keys to values, and can only
+//! generate maps. `lines` files hold one key on each line, and only blank
+//! lines are skipped, so keys may start with `#`.
+//!
+//! The table is written only if it could be generated: duplicate keys and
+//! keys for which no perfect hash is found are reported as errors.
+#![feature(slicing_syntax)]
+
+extern crate getopts;
+extern crate serialize;
+extern crate phf_codegen;
+extern crate phf_mac;
+extern crate toml;
+
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::io::{mod, File, IoResult};
+use std::os;
+use getopts::{optopt, optflag, OptGroup};
+use serialize::json::{mod, Json};
+
+use phf_codegen::Literal;
+use phf_mac::PhfHash;
+
+/// The contents of one record in the input file.
+struct Record {
+    key: String,
+    // The value as Rust source, if the record has one.
+    value: Option<String>,
+}
+
+struct Args {
+    input: Path,
+    output: Option<Path>,
+    format: String,
+    name: String,
+    key_type: String,
+    value_type: Option<String>,
+    ordered: bool,
+}
+
+fn opts() -> Vec<OptGroup> {
+    vec![
+        optopt("o", "output", "write the module to FILE instead of stdout", "FILE"),
+        optopt("f", "format", "input format: csv, tsv, json, toml or lines (default: from the \
+                               file extension, falling back to lines). Lines starting with \
+                               # are skipped in csv and tsv files", "FORMAT"),
+        optopt("n", "name", "name of the generated static (default: TABLE)", "NAME"),
+        optopt("k", "key-type", "key type: str, char, bool, u8, i8, u16, i16, u32, i32, u64, \
+                                 i64, uint or int (default: str)", "TYPE"),
+        optopt("v", "value-type", "Rust type of the values; values of type str are quoted as \
+                                   string literals, others are copied verbatim. A set is \
+                                   generated if this is omitted", "TYPE"),
+        optflag("", "ordered", "generate an OrderedMap or OrderedSet"),
+        optflag("h", "help", "print this help message"),
+    ]
+}
+
+fn parse_args(args: &[String]) -> Result<Option<Args>, String> {
+    let matches = match getopts::getopts(args, opts()[]) {
+        Ok(matches) => matches,
+        Err(e) => return Err(e.to_string()),
+    };
+
+    if matches.opt_present("h") {
+        return Ok(None);
+    }
+
+    if matches.free.len() != 1 {
+        return Err("expected a single input file".to_string());
+    }
+    let input = Path::new(matches.free[0][]);
+    let format = match matches.opt_str("f") {
+        Some(format) => format,
+        None => input.extension_str().unwrap_or("lines").to_string(),
+    };
+
+    Ok(Some(Args {
+        input: input,
+        output: matches.opt_str("o").map(|o| Path::new(o)),
+        format: format,
+        name: matches.opt_str("n").unwrap_or("TABLE".to_string()),
+        key_type: matches.opt_str("k").unwrap_or("str".to_string()),
+        value_type: matches.opt_str("v"),
+        ordered: matches.opt_present("ordered"),
+    }))
+}
+
+fn is_str(ty: &str) -> bool {
+    ty == "str" || ty == "&'static str"
+}
+
+fn quote(s: &str) -> String {
+    let mut buf = vec![];
+    s.write_literal(&mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+fn read_delimited(contents: &str, delim: char, args: &Args) -> Result<Vec<Record>, String> {
+    let mut records = vec![];
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim_right_chars('\r');
+        if line.trim().is_empty() || line.starts_with("#") {
+            continue;
+        }
+
+        // Only the key is delimited, so values may contain `delim`.
+        let columns = line.splitn(1, delim).map(|c| c.trim()).collect::<Vec<_>>();
+        let expected = if args.value_type.is_some() { 2 } else { 1 };
+        if columns.len() != expected {
+            return Err(format!("{}:{}: expected {} columns, found {}", args.input.display(),
+                               i + 1, expected, columns.len()));
+        }
+
+        let value = match args.value_type {
+            Some(ref ty) if is_str(ty[]) => Some(quote(columns[1])),
+            Some(_) => Some(columns[1].to_string()),
+            None => None,
+        };
+        records.push(Record { key: columns[0].to_string(), value: value });
+    }
+    Ok(records)
+}
+
+// Every non-blank line is a key, including lines starting with `#`.
+fn read_lines(contents: &str) -> Vec<Record> {
+    contents.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| Record { key: line.to_string(), value: None })
+            .collect()
+}
+
+fn json_literal(value: &Json) -> Option<String> {
+    match *value {
+        Json::String(ref s) => Some(quote(s[])),
+        Json::I64(i) => Some(i.to_string()),
+        Json::U64(u) => Some(u.to_string()),
+        Json::F64(f) => Some(f.to_string()),
+        Json::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn read_json(contents: &str, args: &Args) -> Result<Vec<Record>, String> {
+    let json = match json::from_str(contents) {
+        Ok(json) => json,
+        Err(e) => return Err(format!("{}: {}", args.input.display(), e)),
+    };
+
+    match (json, args.value_type.is_some()) {
+        (Json::Object(object), true) => {
+            let mut records = vec![];
+            for (key, value) in object.into_iter() {
+                match json_literal(&value) {
+                    Some(value) => records.push(Record { key: key, value: Some(value) }),
+                    None => {
+                        return Err(format!("{}: value at `{}` is not a string, number or \
+                                            boolean", args.input.display(), key));
+                    }
+                }
+            }
+            Ok(records)
+        }
+        (Json::Array(array), false) => {
+            let mut records = vec![];
+            for (i, value) in array.into_iter().enumerate() {
+                match value {
+                    Json::String(key) => records.push(Record { key: key, value: None }),
+                    _ => {
+                        return Err(format!("{}: element at `[{}]` is not a string",
+                                           args.input.display(), i));
+                    }
+                }
+            }
+            Ok(records)
+        }
+        (_, true) => Err(format!("{}: expected a JSON object", args.input.display())),
+        (_, false) => Err(format!("{}: expected a JSON array", args.input.display())),
+    }
+}
+
+// TOML values are converted like `phf_mac` converts them for `.toml` files.
+fn toml_literal(value: &toml::Value) -> Option<String> {
+    match *value {
+        toml::Value::String(ref s) => Some(quote(s[])),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn read_toml(contents: &str, args: &Args) -> Result<Vec<Record>, String> {
+    let mut parser = toml::Parser::new(contents);
+    let table = match parser.parse() {
+        Some(table) => table,
+        None => {
+            let errors = parser.errors.iter().map(|err| {
+                let (line, col) = parser.to_linecol(err.lo);
+                format!("{}:{}:{}: {}", args.input.display(), line + 1, col + 1, err.desc)
+            }).collect::<Vec<_>>();
+            return Err(errors.connect("\n"));
+        }
+    };
+
+    let mut records = vec![];
+    for (key, value) in table.into_iter() {
+        match toml_literal(&value) {
+            Some(value) => records.push(Record { key: key, value: Some(value) }),
+            None => {
+                return Err(format!("{}: value at `{}` is not a string, number or boolean",
+                                   args.input.display(), key));
+            }
+        }
+    }
+    Ok(records)
+}
+
+fn read_records(args: &Args) -> Result<Vec<Record>, String> {
+    let contents = match File::open(&args.input).read_to_string() {
+        Ok(contents) => contents,
+        Err(e) => return Err(format!("couldn't read {}: {}", args.input.display(), e)),
+    };
+
+    match args.format[] {
+        "csv" => read_delimited(contents[], ',', args),
+        "tsv" => read_delimited(contents[], '\t', args),
+        "json" => read_json(contents[], args),
+        // A TOML document is always a table, so there's no way to list keys alone.
+        "toml" if args.value_type.is_some() => read_toml(contents[], args),
+        "toml" => Err("the toml format can only generate maps".to_string()),
+        // Each line is a key, so values can't be provided.
+        "lines" | "txt" if args.value_type.is_none() => Ok(read_lines(contents[])),
+        "lines" | "txt" => Err("the lines format can only generate sets".to_string()),
+        format => Err(format!("unknown input format `{}`", format)),
+    }
+}
+
+fn write_table<W, K>(w: &mut W, args: &Args, keys: Vec<K>, records: &[Record]) -> IoResult<()>
+        where W: Writer, K: Hash+PhfHash+Eq+Literal {
+    let kind = match (args.ordered, args.value_type.is_some()) {
+        (false, true) => "Map",
+        (false, false) => "Set",
+        (true, true) => "OrderedMap",
+        (true, false) => "OrderedSet",
+    };
+    let key_type = if is_str(args.key_type[]) { "&'static str" } else { args.key_type[] };
+
+    try!(write!(w, "// Generated by phf-gen from {}.\n\n", args.input.display()));
+    match args.value_type {
+        Some(ref ty) => {
+            let value_type = if is_str(ty[]) { "&'static str" } else { ty[] };
+            try!(write!(w, "pub static {}: ::phf::{}<{}, {}> = ", args.name, kind, key_type,
+                        value_type));
+        }
+        None => try!(write!(w, "pub static {}: ::phf::{}<{}> = ", args.name, kind, key_type)),
+    }
+
+    let values = records.iter().map(|r| r.value.as_ref().map(|v| v[]).unwrap_or(""));
+    match kind {
+        "Map" => {
+            let mut builder = phf_codegen::Map::new();
+            for (key, value) in keys.into_iter().zip(values) {
+                builder.entry(key, value);
+            }
+            try!(builder.build(w));
+        }
+        "Set" => {
+            let mut builder = phf_codegen::Set::new();
+            for key in keys.into_iter() {
+                builder.entry(key);
+            }
+            try!(builder.build(w));
+        }
+        "OrderedMap" => {
+            let mut builder = phf_codegen::OrderedMap::new();
+            for (key, value) in keys.into_iter().zip(values) {
+                builder.entry(key, value);
+            }
+            try!(builder.build(w));
+        }
+        _ => {
+            let mut builder = phf_codegen::OrderedSet::new();
+            for key in keys.into_iter() {
+                builder.entry(key);
+            }
+            try!(builder.build(w));
+        }
+    }
+    write!(w, ";\n")
+}
+
+fn check_duplicates<K>(keys: &[K], records: &[Record], args: &Args) -> Result<(), String>
+        where K: Hash+Eq {
+    let mut seen = HashSet::new();
+    for (key, record) in keys.iter().zip(records.iter()) {
+        if !seen.insert(key) {
+            return Err(format!("{}: duplicate key `{}`", args.input.display(), record.key));
+        }
+    }
+    Ok(())
+}
+
+fn build_table<W, K>(w: &mut W, args: &Args, keys: Vec<K>, records: &[Record])
+                     -> Result<(), String> where W: Writer, K: Hash+PhfHash+Eq+Literal {
+    try!(check_duplicates(keys[], records, args));
+    write_table(w, args, keys, records).map_err(|e| e.to_string())
+}
+
+fn parse_keys<K>(records: &[Record], args: &Args) -> Result<Vec<K>, String> where K: FromStr {
+    let mut keys = vec![];
+    for record in records.iter() {
+        match from_str(record.key[]) {
+            Some(key) => keys.push(key),
+            None => {
+                return Err(format!("{}: `{}` is not a valid {} key", args.input.display(),
+                                   record.key, args.key_type));
+            }
+        }
+    }
+    Ok(keys)
+}
+
+fn generate<W>(w: &mut W, args: &Args, records: &[Record]) -> Result<(), String> where W: Writer {
+    macro_rules! generate(
+        ($t:ty) => ({
+            let keys = try!(parse_keys::<$t>(records, args));
+            build_table(w, args, keys, records)
+        })
+    )
+
+    match args.key_type[] {
+        "str" | "&'static str" => {
+            let keys = records.iter().map(|r| r.key[]).collect();
+            build_table(w, args, keys, records)
+        }
+        "char" => {
+            let mut keys = vec![];
+            for record in records.iter() {
+                let mut chars = record.key[].chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => keys.push(c),
+                    _ => {
+                        return Err(format!("{}: `{}` is not a valid char key",
+                                           args.input.display(), record.key));
+                    }
+                }
+            }
+            build_table(w, args, keys, records)
+        }
+        "bool" => generate!(bool),
+        "u8" => generate!(u8),
+        "i8" => generate!(i8),
+        "u16" => generate!(u16),
+        "i16" => generate!(i16),
+        "u32" => generate!(u32),
+        "i32" => generate!(i32),
+        "u64" => generate!(u64),
+        "i64" => generate!(i64),
+        "uint" => generate!(uint),
+        "int" => generate!(int),
+        ty => Err(format!("unsupported key type `{}`", ty)),
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let records = try!(read_records(args));
+
+    // The table is generated in memory first so that nothing is written if
+    // generation fails.
+    let mut buf = vec![];
+    try!(generate(&mut buf, args, records[]));
+
+    match args.output {
+        Some(ref path) => match File::create(path).write(buf[]) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!("couldn't write {}: {}", path.display(), e)),
+        },
+        None => io::stdout().write(buf[]).map_err(|e| format!("couldn't write output: {}", e)),
+    }
+}
+
+fn main() {
+    let args = os::args();
+    let usage = getopts::usage(format!("Usage: {} [options] INPUT", args[0])[], opts()[]);
+
+    let result = match parse_args(args.slice_from(1)) {
+        Ok(Some(args)) => run(&args),
+        Ok(None) => {
+            println!("{}", usage);
+            return;
+        }
+        Err(e) => Err(format!("{}\n\n{}", e, usage)),
+    };
+
+    match result {
+        Ok(()) => {}
+        Err(e) => {
+            let _ = writeln!(&mut io::stderr(), "phf-gen: {}", e);
+            os::set_exit_status(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{File, TempDir};
+
+    use super::{parse_args, run};
+
+    // Runs phf-gen on an input file `name` with the given contents, returning
+    // the generated module.
+    fn generate(name: &str, contents: &str, args: &[&str]) -> Result<String, String> {
+        let dir = TempDir::new("phf-gen").unwrap();
+        let input = dir.path().join(name);
+        File::create(&input).write_str(contents).unwrap();
+        let output = dir.path().join("out.rs");
+
+        let mut argv = args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        argv.push("-o".to_string());
+        argv.push(output.as_str().unwrap().to_string());
+        argv.push(input.as_str().unwrap().to_string());
+        let args = parse_args(argv[]).unwrap().unwrap();
+
+        let result = run(&args);
+        if result.is_err() {
+            assert!(!output.exists());
+        }
+        try!(result);
+        Ok(File::open(&output).read_to_string().unwrap())
+    }
+
+    #[test]
+    fn test_csv() {
+        let out = generate("codes.csv", "# code,name\nok,200\n\nnot_found,404\n",
+                           &["-v", "u16", "-n", "CODES"]).unwrap();
+        assert!(out[].contains("pub static CODES: ::phf::Map<&'static str, u16> = ::phf::Map {"));
+        assert!(out[].contains("(\"ok\", 200),"));
+        assert!(out[].contains("(\"not_found\", 404),"));
+        assert!(!out[].contains("code,name"));
+    }
+
+    #[test]
+    fn test_csv_value_with_delimiter() {
+        let out = generate("ops.csv", "add,Op::Binary(1, 2)\ngreet,\"Hello, world\"\n",
+                           &["-v", "Op"]).unwrap();
+        assert!(out[].contains("(\"add\", Op::Binary(1, 2)),"));
+        assert!(out[].contains("(\"greet\", \"Hello, world\"),"));
+
+        let out = generate("greetings.csv", "en,Hello, world\n", &["-v", "str"]).unwrap();
+        assert!(out[].contains("(\"en\", \"Hello, world\"),"));
+    }
+
+    #[test]
+    fn test_tsv() {
+        let out = generate("numbers.tsv", "1\tone\r\n2\ttwo\r\n",
+                           &["-k", "u32", "-v", "str", "--ordered"]).unwrap();
+        assert!(out[].contains("pub static TABLE: ::phf::OrderedMap<u32, &'static str> = \
+                                ::phf::OrderedMap {"));
+        assert!(out[].contains("(1u32, \"one\"),"));
+        assert!(out[].contains("(2u32, \"two\"),"));
+    }
+
+    #[test]
+    fn test_json_map() {
+        let out = generate("flags.json", r#"{"x": true, "y": false}"#, &["-v", "bool"]).unwrap();
+        assert!(out[].contains("pub static TABLE: ::phf::Map<&'static str, bool> = ::phf::Map {"));
+        assert!(out[].contains("(\"x\", true),"));
+        assert!(out[].contains("(\"y\", false),"));
+    }
+
+    #[test]
+    fn test_json_set() {
+        let out = generate("names.json", r#"["x", "y"]"#, &["-k", "char"]).unwrap();
+        assert!(out[].contains("pub static TABLE: ::phf::Set<char> = ::phf::Set { map: \
+                                ::phf::Map {"));
+        assert!(out[].contains("('x', ()),"));
+        assert!(out[].contains("('y', ()),"));
+    }
+
+    #[test]
+    fn test_toml() {
+        let out = generate("limits.toml", "small = 10\nname = \"a, b\"\nratio = 0.5\n",
+                           &["-v", "Limit"]).unwrap();
+        assert!(out[].contains("pub static TABLE: ::phf::Map<&'static str, Limit> = ::phf::Map {"));
+        assert!(out[].contains("(\"small\", 10),"));
+        assert!(out[].contains("(\"name\", \"a, b\"),"));
+        assert!(out[].contains("(\"ratio\", 0.5),"));
+    }
+
+    #[test]
+    fn test_toml_set() {
+        let err = generate("names.toml", "x = 1\n", &[]).unwrap_err();
+        assert_eq!(err[], "the toml format can only generate maps");
+    }
+
+    #[test]
+    fn test_lines() {
+        let out = generate("words.txt", "#hash\nplain\n\n", &[]).unwrap();
+        assert!(out[].contains("pub static TABLE: ::phf::Set<&'static str> = ::phf::Set {"));
+        assert!(out[].contains("(\"#hash\", ()),"));
+        assert!(out[].contains("(\"plain\", ()),"));
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let err = generate("dups.csv", "a,1\nb,2\na,3\n", &["-v", "u8"]).unwrap_err();
+        assert!(err[].ends_with("duplicate key `a`"));
+    }
+
+    #[test]
+    fn test_duplicate_parsed_keys() {
+        let err = generate("dups.csv", "1\n01\n", &["-k", "u8"]).unwrap_err();
+        assert!(err[].ends_with("duplicate key `01`"));
+    }
+}
@@ -0,0 +1,410 @@
+//! A set of builders to generate Rust source for PHF data structures at
+//! compile time.
+//!
+//! The provided builders are intended to be used in a Cargo build script to
+//! generate a Rust source file that will be included in a library at build
+//! time. This allows PHF data structures to be used without depending on the
+//! `phf_mac` compiler plugin at the use site. The generated tables are
+//! identical to the ones `phf_mac` would produce for the same keys.
+//!
+//! # Examples
+//!
+//! build.rs
+//!
+//! ```rust,no_run
+//! extern crate phf_codegen;
+//!
+//! use std::io::{BufferedWriter, File};
+//! use std::os;
+//!
+//! fn main() {
+//!     let path = Path::new(os::getenv("OUT_DIR").unwrap()).join("codegen.rs");
+//!     let mut file = BufferedWriter::new(File::create(&path).unwrap());
+//!
+//!     write!(&mut file, "static KEYWORDS: phf::Map<&'static str, Keyword> = ").unwrap();
+//!     phf_codegen::Map::new()
+//!         .entry("loop", "Keyword::Loop")
+//!         .entry("continue", "Keyword::Continue")
+//!         .entry("break", "Keyword::Break")
+//!         .entry("fn", "Keyword::Fn")
+//!         .entry("extern", "Keyword::Extern")
+//!         .build(&mut file)
+//!         .unwrap();
+//!     write!(&mut file, ";\n").unwrap();
+//! }
+//! ```
+//!
+//! lib.rs
+//!
+//! ```ignore
+//! extern crate phf;
+//!
+//! #[deriving(Clone)]
+//! enum Keyword {
+//!     Loop,
+//!     Continue,
+//!     Break,
+//!     Fn,
+//!     Extern,
+//! }
+//!
+//! include!(concat!(env!("OUT_DIR"), "/codegen.rs"))
+//!
+//! pub fn parse_keyword(keyword: &str) -> Option<Keyword> {
+//!     KEYWORDS.get_equiv(keyword).map(|t| t.clone())
+//! }
+//! ```
+//!
+//! Like the options of the `phf_mac` macros, the `hasher` and `params`
+//! methods of the builders select the hash function and the parameters used
+//! to generate the table. The static's type must then name the same hasher,
+//! for example `phf::Map<&'static str, Keyword, phf::FxHasher>`.
+#![doc(html_root_url="http://sfackler.github.io/doc")]
+#![feature(macro_rules)]
+
+extern crate phf_mac;
+
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::io::{IoError, IoResult, InvalidInput, Writer};
+
+use phf_mac::PhfHash;
+use phf_mac::util::{HashState, find_collisions};
+
+pub use phf_mac::util::{Hasher, HashParams, DEFAULT_PARAMS};
+
+/// A key type which can be written out as a Rust literal.
+pub trait Literal {
+    /// Writes the literal form of `self` to `w`.
+    fn write_literal<W: Writer>(&self, w: &mut W) -> IoResult<()>;
+}
+
+impl<'a> Literal for &'a str {
+    fn write_literal<W: Writer>(&self, w: &mut W) -> IoResult<()> {
+        write!(w, "\"{}\"", self.escape_default())
+    }
+}
+
+impl<'a> Literal for &'a [u8] {
+    fn write_literal<W: Writer>(&self, w: &mut W) -> IoResult<()> {
+        try!(write!(w, "b\""));
+        for b in self.iter() {
+            try!(write!(w, "\\x{:02x}", *b));
+        }
+        write!(w, "\"")
+    }
+}
+
+impl Literal for char {
+    fn write_literal<W: Writer>(&self, w: &mut W) -> IoResult<()> {
+        try!(write!(w, "'"));
+        for c in self.escape_default() {
+            try!(write!(w, "{}", c));
+        }
+        write!(w, "'")
+    }
+}
+
+impl Literal for bool {
+    fn write_literal<W: Writer>(&self, w: &mut W) -> IoResult<()> {
+        write!(w, "{}", *self)
+    }
+}
+
+macro_rules! int_literal(
+    ($t:ty, $suffix:expr) => (
+        impl Literal for $t {
+            fn write_literal<W: Writer>(&self, w: &mut W) -> IoResult<()> {
+                write!(w, "{}{}", *self, $suffix)
+            }
+        }
+    )
+)
+
+int_literal!(u8, "u8")
+int_literal!(i8, "i8")
+int_literal!(u16, "u16")
+int_literal!(i16, "i16")
+int_literal!(u32, "u32")
+int_literal!(i32, "i32")
+int_literal!(u64, "u64")
+int_literal!(i64, "i64")
+int_literal!(uint, "u")
+int_literal!(int, "i")
+
+fn literal<K>(key: &K) -> String where K: Literal {
+    let mut buf = vec![];
+    key.write_literal(&mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+fn check_duplicates<K>(keys: &[K]) -> IoResult<()> where K: Hash+Eq+Literal {
+    let mut set = HashSet::new();
+    for key in keys.iter() {
+        if !set.insert(key) {
+            return Err(IoError {
+                kind: InvalidInput,
+                desc: "duplicate key",
+                detail: Some(format!("`{}`", literal(key))),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn generate_hash<K>(keys: &[K], hasher: &Hasher, params: &HashParams) -> IoResult<HashState>
+        where K: Hash+PhfHash+Eq+Literal {
+    if params.lambda == 0 || !(params.load_factor > 0.0 && params.load_factor <= 1.0) {
+        return Err(IoError {
+            kind: InvalidInput,
+            desc: "invalid hash parameters",
+            detail: Some("lambda must be positive and load_factor must be greater than 0 and \
+                          at most 1".to_string()),
+        });
+    }
+    try!(check_duplicates(keys));
+    match hasher.generate_hash(keys, params) {
+        Some(state) => Ok(state),
+        None => {
+            let mut detail = format!("no perfect hash found after {} attempts",
+                                     params.max_attempts);
+            for group in find_collisions(keys).iter() {
+                let names = group.iter().map(|&i| format!("`{}`", literal(&keys[i])))
+                                 .collect::<Vec<_>>();
+                detail.push_str(format!("; keys {} have colliding hashes",
+                                        names.connect(", "))[]);
+            }
+            Err(IoError {
+                kind: InvalidInput,
+                desc: "unable to generate a perfect hash",
+                detail: Some(detail),
+            })
+        }
+    }
+}
+
+// Writes a `phf::Map` or `phf::OrderedMap`, which share a layout, with the
+// struct name `name`.
+fn write_map<W, K>(w: &mut W, name: &str, keys: &[K], values: &[String], hasher: &Hasher,
+                   state: &HashState) -> IoResult<()> where W: Writer, K: Literal {
+    try!(write!(w, "::phf::{} {{\n    hasher: ::phf::{},\n", name, hasher.type_name()));
+    try!(write!(w, "    key: {},\n    disps: &[\n", state.key));
+    for &(d1, d2) in state.disps.iter() {
+        try!(write!(w, "        ({}, {}),\n", d1, d2));
+    }
+    try!(write!(w, "    ],\n    idxs: &[\n"));
+    for &idx in state.map.iter() {
+        try!(write!(w, "        {},\n", idx));
+    }
+    try!(write!(w, "    ],\n    entries: &[\n"));
+    for (key, value) in keys.iter().zip(values.iter()) {
+        try!(write!(w, "        ("));
+        try!(key.write_literal(w));
+        try!(write!(w, ", {}),\n", value));
+    }
+    write!(w, "    ],\n}}")
+}
+
+/// A builder for the `phf::Map` type.
+pub struct Map<K> {
+    keys: Vec<K>,
+    values: Vec<String>,
+    hasher: Hasher,
+    params: HashParams,
+}
+
+impl<K> Map<K> where K: Hash+PhfHash+Eq+Literal {
+    /// Creates a new `phf::Map` builder.
+    pub fn new() -> Map<K> {
+        Map {
+            keys: vec![],
+            values: vec![],
+            hasher: Hasher::Xx,
+            params: DEFAULT_PARAMS,
+        }
+    }
+
+    /// Adds an entry to the builder.
+    ///
+    /// `value` will be written exactly as provided in the constructed source.
+    pub fn entry(&mut self, key: K, value: &str) -> &mut Map<K> {
+        self.keys.push(key);
+        self.values.push(value.to_string());
+        self
+    }
+
+    /// Sets the hash function used to build the table, which defaults to
+    /// `Hasher::Xx`.
+    pub fn hasher(&mut self, hasher: Hasher) -> &mut Map<K> {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Sets the parameters used to generate the hash, which default to
+    /// `DEFAULT_PARAMS`.
+    pub fn params(&mut self, params: HashParams) -> &mut Map<K> {
+        self.params = params;
+        self
+    }
+
+    /// Constructs a `phf::Map`, outputting Rust source to the provided
+    /// writer.
+    ///
+    /// Returns an `InvalidInput` error if there are any duplicate keys, if
+    /// the hash parameters are invalid, or if a perfect hash could not be
+    /// found for the keys.
+    pub fn build<W: Writer>(&self, w: &mut W) -> IoResult<()> {
+        let state = try!(generate_hash(self.keys[], &self.hasher, &self.params));
+        write_map(w, "Map", self.keys[], self.values[], &self.hasher, &state)
+    }
+}
+
+/// A builder for the `phf::Set` type.
+pub struct Set<T> {
+    map: Map<T>,
+}
+
+impl<T> Set<T> where T: Hash+PhfHash+Eq+Literal {
+    /// Constructs a new `phf::Set` builder.
+    pub fn new() -> Set<T> {
+        Set {
+            map: Map::new(),
+        }
+    }
+
+    /// Adds an entry to the builder.
+    pub fn entry(&mut self, entry: T) -> &mut Set<T> {
+        self.map.entry(entry, "()");
+        self
+    }
+
+    /// Sets the hash function used to build the table, which defaults to
+    /// `Hasher::Xx`.
+    pub fn hasher(&mut self, hasher: Hasher) -> &mut Set<T> {
+        self.map.hasher(hasher);
+        self
+    }
+
+    /// Sets the parameters used to generate the hash, which default to
+    /// `DEFAULT_PARAMS`.
+    pub fn params(&mut self, params: HashParams) -> &mut Set<T> {
+        self.map.params(params);
+        self
+    }
+
+    /// Constructs a `phf::Set`, outputting Rust source to the provided
+    /// writer.
+    ///
+    /// Returns an `InvalidInput` error if there are any duplicate entries, if
+    /// the hash parameters are invalid, or if a perfect hash could not be
+    /// found for the entries.
+    pub fn build<W: Writer>(&self, w: &mut W) -> IoResult<()> {
+        let map = &self.map;
+        let state = try!(generate_hash(map.keys[], &map.hasher, &map.params));
+        try!(write!(w, "::phf::Set {{ map: "));
+        try!(write_map(w, "Map", map.keys[], map.values[], &map.hasher, &state));
+        write!(w, " }}")
+    }
+}
+
+/// A builder for the `phf::OrderedMap` type.
+pub struct OrderedMap<K> {
+    keys: Vec<K>,
+    values: Vec<String>,
+    hasher: Hasher,
+    params: HashParams,
+}
+
+impl<K> OrderedMap<K> where K: Hash+PhfHash+Eq+Literal {
+    /// Constructs a new `phf::OrderedMap` builder.
+    pub fn new() -> OrderedMap<K> {
+        OrderedMap {
+            keys: vec![],
+            values: vec![],
+            hasher: Hasher::Xx,
+            params: DEFAULT_PARAMS,
+        }
+    }
+
+    /// Adds an entry to the builder.
+    ///
+    /// `value` will be written exactly as provided in the constructed source.
+    pub fn entry(&mut self, key: K, value: &str) -> &mut OrderedMap<K> {
+        self.keys.push(key);
+        self.values.push(value.to_string());
+        self
+    }
+
+    /// Sets the hash function used to build the table, which defaults to
+    /// `Hasher::Xx`.
+    pub fn hasher(&mut self, hasher: Hasher) -> &mut OrderedMap<K> {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Sets the parameters used to generate the hash, which default to
+    /// `DEFAULT_PARAMS`.
+    pub fn params(&mut self, params: HashParams) -> &mut OrderedMap<K> {
+        self.params = params;
+        self
+    }
+
+    /// Constructs a `phf::OrderedMap`, outputting Rust source to the
+    /// provided writer.
+    ///
+    /// Returns an `InvalidInput` error if there are any duplicate keys, if
+    /// the hash parameters are invalid, or if a perfect hash could not be
+    /// found for the keys.
+    pub fn build<W: Writer>(&self, w: &mut W) -> IoResult<()> {
+        let state = try!(generate_hash(self.keys[], &self.hasher, &self.params));
+        write_map(w, "OrderedMap", self.keys[], self.values[], &self.hasher, &state)
+    }
+}
+
+/// A builder for the `phf::OrderedSet` type.
+pub struct OrderedSet<T> {
+    map: OrderedMap<T>,
+}
+
+impl<T> OrderedSet<T> where T: Hash+PhfHash+Eq+Literal {
+    /// Constructs a new `phf::OrderedSet` builder.
+    pub fn new() -> OrderedSet<T> {
+        OrderedSet {
+            map: OrderedMap::new(),
+        }
+    }
+
+    /// Adds an entry to the builder.
+    pub fn entry(&mut self, entry: T) -> &mut OrderedSet<T> {
+        self.map.entry(entry, "()");
+        self
+    }
+
+    /// Sets the hash function used to build the table, which defaults to
+    /// `Hasher::Xx`.
+    pub fn hasher(&mut self, hasher: Hasher) -> &mut OrderedSet<T> {
+        self.map.hasher(hasher);
+        self
+    }
+
+    /// Sets the parameters used to generate the hash, which default to
+    /// `DEFAULT_PARAMS`.
+    pub fn params(&mut self, params: HashParams) -> &mut OrderedSet<T> {
+        self.map.params(params);
+        self
+    }
+
+    /// Constructs a `phf::OrderedSet`, outputting Rust source to the
+    /// provided writer.
+    ///
+    /// Returns an `InvalidInput` error if there are any duplicate entries, if
+    /// the hash parameters are invalid, or if a perfect hash could not be
+    /// found for the entries.
+    pub fn build<W: Writer>(&self, w: &mut W) -> IoResult<()> {
+        let map = &self.map;
+        let state = try!(generate_hash(map.keys[], &map.hasher, &map.params));
+        try!(write!(w, "::phf::OrderedSet {{ map: "));
+        try!(write_map(w, "OrderedMap", map.keys[], map.values[], &map.hasher, &state));
+        write!(w, " }}")
+    }
+}
@@ -0,0 +1,70 @@
+extern crate phf_codegen;
+
+use std::io::{BufferedWriter, File};
+use std::os;
+
+fn main() {
+    let path = Path::new(os::getenv("OUT_DIR").unwrap()).join("codegen.rs");
+    let mut file = BufferedWriter::new(File::create(&path).unwrap());
+
+    write!(&mut file, "static MAP: ::phf::Map<u32, &'static str> = ").unwrap();
+    phf_codegen::Map::new()
+        .entry(1u32, "\"a\"")
+        .entry(2u32, "\"b\"")
+        .entry(3u32, "\"c\"")
+        .build(&mut file)
+        .unwrap();
+    write!(&mut file, ";\n").unwrap();
+
+    write!(&mut file, "static SET: ::phf::Set<u32> = ").unwrap();
+    phf_codegen::Set::new()
+        .entry(1u32)
+        .entry(2u32)
+        .entry(3u32)
+        .build(&mut file)
+        .unwrap();
+    write!(&mut file, ";\n").unwrap();
+
+    write!(&mut file, "static ORDERED_MAP: ::phf::OrderedMap<u32, &'static str> = ").unwrap();
+    phf_codegen::OrderedMap::new()
+        .entry(1u32, "\"a\"")
+        .entry(2u32, "\"b\"")
+        .entry(3u32, "\"c\"")
+        .build(&mut file)
+        .unwrap();
+    write!(&mut file, ";\n").unwrap();
+
+    write!(&mut file, "static ORDERED_SET: ::phf::OrderedSet<u32> = ").unwrap();
+    phf_codegen::OrderedSet::new()
+        .entry(1u32)
+        .entry(2u32)
+        .entry(3u32)
+        .build(&mut file)
+        .unwrap();
+    write!(&mut file, ";\n").unwrap();
+
+    write!(&mut file, "static STR_KEYS: ::phf::Map<&'static str, u32> = ").unwrap();
+    phf_codegen::Map::new()
+        .entry("a", "1")
+        .entry("b", "2")
+        .entry("c\"\n", "3")
+        .build(&mut file)
+        .unwrap();
+    write!(&mut file, ";\n").unwrap();
+
+    write!(&mut file, "static FX_MAP: ::phf::Map<u32, &'static str, ::phf::FxHasher> = ")
+        .unwrap();
+    phf_codegen::Map::new()
+        .entry(1u32, "\"a\"")
+        .entry(2u32, "\"b\"")
+        .entry(3u32, "\"c\"")
+        .hasher(phf_codegen::Hasher::Fx)
+        .params(phf_codegen::HashParams {
+            lambda: 1,
+            load_factor: 0.5,
+            max_attempts: 100,
+        })
+        .build(&mut file)
+        .unwrap();
+    write!(&mut file, ";\n").unwrap();
+}
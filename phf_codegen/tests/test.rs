@@ -0,0 +1,96 @@
+extern crate phf_codegen;
+extern crate phf_mac;
+
+use std::hash::Writer;
+use std::io::{mod, IoResult, InvalidInput};
+
+use phf_codegen::Literal;
+use phf_mac::PhfHash;
+
+// A key which hashes only its tens digit, so that keys with the same tens
+// digit collide under every seed.
+#[deriving(PartialEq, Eq, Hash)]
+struct Tens(u32);
+
+impl PhfHash for Tens {
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        (self.0 / 10).phf_hash_into(state)
+    }
+}
+
+impl Literal for Tens {
+    fn write_literal<W: io::Writer>(&self, w: &mut W) -> IoResult<()> {
+        write!(w, "Tens({})", self.0)
+    }
+}
+
+#[test]
+fn test_duplicate_keys() {
+    let mut buf = vec![];
+    let err = phf_codegen::Map::new()
+        .entry("a", "1")
+        .entry("b", "2")
+        .entry("a", "3")
+        .build(&mut buf)
+        .unwrap_err();
+    assert_eq!(InvalidInput, err.kind);
+    assert_eq!("duplicate key", err.desc);
+    assert_eq!(Some("`\"a\"`".to_string()), err.detail);
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn test_duplicate_set_entries() {
+    let mut buf = vec![];
+    let err = phf_codegen::OrderedSet::new()
+        .entry(1u32)
+        .entry(1u32)
+        .build(&mut buf)
+        .unwrap_err();
+    assert_eq!(InvalidInput, err.kind);
+    assert_eq!(Some("`1u32`".to_string()), err.detail);
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn test_hasher() {
+    let mut buf = vec![];
+    phf_codegen::Set::new()
+        .entry(1u32)
+        .hasher(phf_codegen::Hasher::Sip13)
+        .build(&mut buf)
+        .unwrap();
+    let out = String::from_utf8(buf).unwrap();
+    assert!(out[].contains("hasher: ::phf::Sip13Hasher,"));
+}
+
+#[test]
+fn test_invalid_params() {
+    let mut buf = vec![];
+    let err = phf_codegen::Map::new()
+        .entry(1u32, "()")
+        .params(phf_codegen::HashParams { lambda: 0, ..phf_codegen::DEFAULT_PARAMS })
+        .build(&mut buf)
+        .unwrap_err();
+    assert_eq!(InvalidInput, err.kind);
+    assert_eq!("invalid hash parameters", err.desc);
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn test_colliding_keys() {
+    let mut buf = vec![];
+    let err = phf_codegen::Map::new()
+        .entry(Tens(11), "1")
+        .entry(Tens(25), "2")
+        .entry(Tens(12), "3")
+        .params(phf_codegen::HashParams { max_attempts: 10, ..phf_codegen::DEFAULT_PARAMS })
+        .build(&mut buf)
+        .unwrap_err();
+    assert_eq!(InvalidInput, err.kind);
+    assert_eq!("unable to generate a perfect hash", err.desc);
+    assert_eq!(Some("no perfect hash found after 10 attempts; keys `Tens(11)`, `Tens(12)` \
+                     have colliding hashes".to_string()),
+               err.detail);
+    assert!(buf.is_empty());
+}
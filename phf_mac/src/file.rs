@@ -0,0 +1,305 @@
+//! Loading of map entries from data files.
+use std::io::File;
+
+use serialize::json;
+use serialize::json::Json;
+
+use syntax::ast::{mod, Expr, Ty};
+use syntax::codemap::Span;
+use syntax::ext::base::ExtCtxt;
+use syntax::ext::build::AstBuilder;
+use syntax::parse;
+use syntax::parse::token;
+use syntax::print::pprust;
+use syntax::ptr::P;
+use toml;
+
+use util::{Entry, Key};
+
+macro_rules! unsigned_key(
+    ($cx:expr, $sp:expr, $text:expr, $t:ty, $variant:ident, $store:ty, $ty:expr) => (
+        from_str::<$t>($text).map(|v| {
+            let lit = ast::LitInt(v as u64, ast::UnsignedIntLit($ty));
+            (Key::$variant(v as $store), $cx.expr_lit($sp, lit))
+        })
+    )
+)
+
+macro_rules! signed_key(
+    ($cx:expr, $sp:expr, $text:expr, $t:ty, $variant:ident, $store:ty, $ty:expr) => (
+        from_str::<$t>($text).map(|v| {
+            let lit = ast::LitInt((v as i64).abs() as u64, ast::SignedIntLit($ty, ast::Plus));
+            let expr = $cx.expr_lit($sp, lit);
+            let expr = if v < 0 { $cx.expr_unary($sp, ast::UnNeg, expr) } else { expr };
+            (Key::$variant(v as $store), expr)
+        })
+    )
+)
+
+/// Reads map entries from the file at `path`, relative to the file
+/// containing `sp`.
+///
+/// `.json` and `.toml` files must hold a single object or table mapping
+/// string keys to scalar values. Any other file is read as delimited text,
+/// as described by `parse_delimited`. Keys are converted according to
+/// `key_ty`.
+pub fn parse_entries(cx: &mut ExtCtxt, sp: Span, path: &Path, key_ty: &Ty, value_ty: &Ty)
+                     -> Option<Vec<Entry>> {
+    let path = if path.is_absolute() {
+        path.clone()
+    } else {
+        let mut cu = Path::new(cx.codemap().span_to_filename(sp));
+        cu.pop();
+        cu.push(path);
+        cu
+    };
+
+    let contents = match File::open(&path).read_to_string() {
+        Ok(contents) => contents,
+        Err(e) => {
+            cx.span_err(sp, format!("couldn't read {}: {}", path.display(), e)[]);
+            return None;
+        }
+    };
+
+    let name = path.display().to_string();
+    let key_ty = pprust::ty_to_string(key_ty);
+    let value_ty = pprust::ty_to_string(value_ty);
+
+    match path.extension_str() {
+        Some("json") => {
+            parse_json(cx, sp, name[], contents[])
+                .and_then(|entries| convert_entries(cx, sp, name[], key_ty[], entries))
+        }
+        Some("toml") => {
+            parse_toml(cx, sp, name[], contents[])
+                .and_then(|entries| convert_entries(cx, sp, name[], key_ty[], entries))
+        }
+        Some("tsv") => parse_delimited(cx, sp, name[], contents[], '\t', key_ty[], value_ty[]),
+        _ => parse_delimited(cx, sp, name[], contents[], ',', key_ty[], value_ty[]),
+    }
+}
+
+/// Reads map entries from delimited text.
+///
+/// Each non-empty line which doesn't start with `#` holds a key and a value
+/// separated by the first `delim` on the line. Values are taken verbatim as string literals if
+/// `value_ty` is `&'static str`, and are otherwise parsed as Rust
+/// expressions.
+fn parse_delimited(cx: &mut ExtCtxt, sp: Span, name: &str, contents: &str, delim: char,
+                   key_ty: &str, value_ty: &str) -> Option<Vec<Entry>> {
+    let mut entries = vec![];
+    let mut bad = false;
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim_right_chars('\r');
+        if line.trim().is_empty() || line.starts_with("#") {
+            continue;
+        }
+
+        let location = format!("{}:{}", name, i + 1);
+        // Only the key is delimited, so values may contain `delim`.
+        let columns = line.splitn(1, delim).map(|c| c.trim()).collect::<Vec<_>>();
+        if columns.len() != 2 {
+            cx.span_err(sp, format!("{}: expected 2 columns, found {}", location,
+                                    columns.len())[]);
+            bad = true;
+            continue;
+        }
+
+        let (key_contents, key) = match parse_key(cx, sp, key_ty, columns[0]) {
+            Some(key) => key,
+            None => {
+                cx.span_err(sp, format!("{}: invalid {} key `{}`", location, key_ty,
+                                        columns[0])[]);
+                bad = true;
+                continue;
+            }
+        };
+
+        let value = parse_value(cx, sp, value_ty, location, columns[1]);
+
+        entries.push(Entry {
+            key_contents: key_contents,
+            key: key,
+            value: value,
+        });
+    }
+
+    if bad {
+        None
+    } else {
+        Some(entries)
+    }
+}
+
+/// A scalar value read from a JSON or TOML file.
+enum Scalar {
+    Str(String),
+    Int(i64),
+    Uint(u64),
+    Float(f64),
+    Bool(bool),
+}
+
+impl Scalar {
+    fn to_expr(&self, cx: &ExtCtxt, sp: Span) -> P<Expr> {
+        match *self {
+            Scalar::Str(ref s) => cx.expr_str(sp, token::intern_and_get_ident(s[])),
+            Scalar::Int(i) if i < 0 => {
+                let lit = ast::LitInt((-i) as u64, ast::UnsuffixedIntLit(ast::Plus));
+                cx.expr_unary(sp, ast::UnNeg, cx.expr_lit(sp, lit))
+            }
+            Scalar::Int(i) => Scalar::Uint(i as u64).to_expr(cx, sp),
+            Scalar::Uint(u) => cx.expr_lit(sp, ast::LitInt(u, ast::UnsuffixedIntLit(ast::Plus))),
+            Scalar::Float(f) if f < 0. => {
+                cx.expr_unary(sp, ast::UnNeg, Scalar::Float(-f).to_expr(cx, sp))
+            }
+            Scalar::Float(f) => {
+                let lit = ast::LitFloatUnsuffixed(token::intern_and_get_ident(f.to_string()[]));
+                cx.expr_lit(sp, lit)
+            }
+            Scalar::Bool(b) => cx.expr_bool(sp, b),
+        }
+    }
+}
+
+/// Converts the key/value pairs of a JSON object or TOML table into
+/// entries.
+///
+/// A `None` value marks one which isn't a scalar.
+fn convert_entries(cx: &mut ExtCtxt, sp: Span, name: &str, key_ty: &str,
+                   pairs: Vec<(String, Option<Scalar>)>) -> Option<Vec<Entry>> {
+    let mut entries = vec![];
+    let mut bad = false;
+    for (k, v) in pairs.into_iter() {
+        let (key_contents, key) = match parse_key(cx, sp, key_ty, k[]) {
+            Some(key) => key,
+            None => {
+                cx.span_err(sp, format!("{}: key `{}` is not a valid {} key", name, k,
+                                        key_ty)[]);
+                bad = true;
+                continue;
+            }
+        };
+
+        let value = match v {
+            Some(v) => v.to_expr(cx, sp),
+            None => {
+                cx.span_err(sp, format!("{}: value at `{}` is not a string, number or \
+                                         boolean", name, k)[]);
+                bad = true;
+                continue;
+            }
+        };
+
+        entries.push(Entry {
+            key_contents: key_contents,
+            key: key,
+            value: value,
+        });
+    }
+
+    if bad {
+        None
+    } else {
+        Some(entries)
+    }
+}
+
+fn parse_json(cx: &mut ExtCtxt, sp: Span, name: &str, contents: &str)
+              -> Option<Vec<(String, Option<Scalar>)>> {
+    let object = match json::from_str(contents) {
+        Ok(Json::Object(object)) => object,
+        Ok(_) => {
+            cx.span_err(sp, format!("{}: expected a JSON object", name)[]);
+            return None;
+        }
+        Err(e) => {
+            cx.span_err(sp, format!("{}: {}", name, e)[]);
+            return None;
+        }
+    };
+
+    Some(object.into_iter().map(|(k, v)| {
+        let v = match v {
+            Json::String(s) => Some(Scalar::Str(s)),
+            Json::I64(i) => Some(Scalar::Int(i)),
+            Json::U64(u) => Some(Scalar::Uint(u)),
+            Json::F64(f) => Some(Scalar::Float(f)),
+            Json::Boolean(b) => Some(Scalar::Bool(b)),
+            _ => None,
+        };
+        (k, v)
+    }).collect())
+}
+
+fn parse_toml(cx: &mut ExtCtxt, sp: Span, name: &str, contents: &str)
+              -> Option<Vec<(String, Option<Scalar>)>> {
+    let mut parser = toml::Parser::new(contents);
+    let table = match parser.parse() {
+        Some(table) => table,
+        None => {
+            for err in parser.errors.iter() {
+                let (line, col) = parser.to_linecol(err.lo);
+                cx.span_err(sp, format!("{}:{}:{}: {}", name, line + 1, col + 1, err.desc)[]);
+            }
+            return None;
+        }
+    };
+
+    Some(table.into_iter().map(|(k, v)| {
+        let v = match v {
+            toml::Value::String(s) => Some(Scalar::Str(s)),
+            toml::Value::Integer(i) => Some(Scalar::Int(i)),
+            toml::Value::Float(f) => Some(Scalar::Float(f)),
+            toml::Value::Boolean(b) => Some(Scalar::Bool(b)),
+            _ => None,
+        };
+        (k, v)
+    }).collect())
+}
+
+/// Converts the text of a key into a `Key` of the type named `ty`, along
+/// with the literal expression for it.
+pub fn parse_key(cx: &ExtCtxt, sp: Span, ty: &str, text: &str) -> Option<(Key, P<Expr>)> {
+    match ty {
+        "&'static str" => {
+            let s = token::intern_and_get_ident(text);
+            Some((Key::Str(s.clone()), cx.expr_lit(sp, ast::LitStr(s, ast::CookedStr))))
+        }
+        "char" => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some((Key::Char(c), cx.expr_lit(sp, ast::LitChar(c)))),
+                _ => None,
+            }
+        }
+        "bool" => from_str::<bool>(text).map(|b| (Key::Bool(b), cx.expr_bool(sp, b))),
+        "u8" => unsigned_key!(cx, sp, text, u8, U8, u8, ast::TyU8),
+        "u16" => unsigned_key!(cx, sp, text, u16, U16, u16, ast::TyU16),
+        "u32" => unsigned_key!(cx, sp, text, u32, U32, u32, ast::TyU32),
+        "u64" => unsigned_key!(cx, sp, text, u64, U64, u64, ast::TyU64),
+        "uint" => unsigned_key!(cx, sp, text, uint, Uint, u64, ast::TyU),
+        "i8" => signed_key!(cx, sp, text, i8, I8, i8, ast::TyI8),
+        "i16" => signed_key!(cx, sp, text, i16, I16, i16, ast::TyI16),
+        "i32" => signed_key!(cx, sp, text, i32, I32, i32, ast::TyI32),
+        "i64" => signed_key!(cx, sp, text, i64, I64, i64, ast::TyI64),
+        "int" => signed_key!(cx, sp, text, int, Int, i64, ast::TyI),
+        _ => None,
+    }
+}
+
+/// Converts the text of a value into an expression.
+///
+/// `location` names the value's source in any parse errors.
+pub fn parse_value(cx: &ExtCtxt, sp: Span, ty: &str, location: String, text: &str) -> P<Expr> {
+    if ty == "&'static str" {
+        return cx.expr_str(sp, token::intern_and_get_ident(text));
+    }
+
+    let mut parser = parse::new_parser_from_source_str(cx.parse_sess(), cx.cfg(), location,
+                                                       text.to_string());
+    let expr = parser.parse_expr();
+    parser.expect(&token::Eof);
+    expr
+}
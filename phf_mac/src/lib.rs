@@ -1,35 +1,173 @@
 //! Compiler plugin for Rust-PHF
 //!
 //! See the documentation for the `phf` crate for more details.
+//!
+//! The generated tables can be tuned by starting a macro invocation with an
+//! options attribute:
+//!
+//! ```ignore
+//! static MAP: phf::Map<&'static str, int> = phf_map! {
+//!     #[lambda = 3, load_factor = 0.9]
+//!     "hello" => 10,
+//!     "world" => 11,
+//! };
+//! ```
+//!
+//! * `lambda` - the average number of keys in each displacement bucket.
+//!     Smaller values produce a larger displacement table but make
+//!     generation faster. Defaults to 5.
+//! * `load_factor` - the ratio of keys to slots in the table, greater than
+//!     0 and at most 1. Lower values make generation faster at the cost of a
+//!     larger table. Defaults to 1.
+//! * `max_attempts` - the number of hash keys to try before giving up.
+//!     Defaults to 1000, or the value of the `PHF_MAX_ATTEMPTS` environment
+//!     variable at compile time.
+//! * `hasher` - the hash function used, which must match the hasher type
+//!     parameter of the generated data structure. One of `"xx"` (the
+//!     default, `phf::XxHasher`), `"sip13"` (`phf::Sip13Hasher`), `"fx"`
+//!     (`phf::FxHasher`) or `"mul_shift"` (`phf::MulShiftHasher`).
+//!
+//! `phf_map_from_file!` builds a `phf::Map` from the entries of a data file,
+//! given its path relative to the invoking source file and the key and value
+//! types. Keys may be strings, chars, bools or integers.
+//!
+//! ```ignore
+//! static CODES: phf::Map<u16, &'static str> =
+//!     phf_map_from_file!("codes.tsv", u16, &'static str);
+//! ```
+//!
+//! `.json` and `.toml` files must contain a single object or table whose
+//! values are strings, numbers or booleans, which are converted into the
+//! corresponding literals. Any other file is read as delimited text: each
+//! line holds a key and a value, separated by a tab in `.tsv` files or a comma
+//! otherwise. Blank lines and lines starting with `#` are skipped. Values are
+//! used as string literals if the value type is `&'static str`, and are
+//! otherwise parsed as Rust expressions. Columns are not quoted: a line is
+//! split at its first separator, so keys may not contain the separator but
+//! values may.
+//!
+//! `phf_bimap!` builds a `phf::BiMap`, taking the same form as `phf_map!`
+//! except that the values must be literals as well. Duplicates are rejected
+//! on both sides.
+//!
+//! `phf_multimap!` builds a `phf::MultiMap`, taking the same form as
+//! `phf_map!` except that keys may be repeated.
+//!
+//! `phf_prefix_map!` builds a `phf::PrefixMap`, taking the same form as
+//! `phf_map!` except that the keys must be string literals.
+//!
+//! `phf_sorted_map!` builds a `phf::SortedMap`, taking the same form as
+//! `phf_map!`. The entries are sorted by key, so the keys must be of a type
+//! whose order is known to the plugin: strings, byte strings, chars, bools or
+//! integers, or tuples or arrays of those.
+//!
+//! `phf_range_map!` builds a `phf::RangeMap` from integer ranges, written
+//! `a..b` to exclude the upper bound or `a...b` to include it. The bounds
+//! must be literals of a single integer type, and ranges may not overlap:
+//!
+//! ```ignore
+//! static CLASSES: phf::RangeMap<u16, &'static str> = phf_range_map! {
+//!     200u16..300u16 => "success",
+//!     400u16...499u16 => "client error",
+//! };
+//! ```
+//!
+//! Variants of a fieldless enum can be used as keys once the enum is marked
+//! with the `#[phf_enum]` attribute, which implements `PhfHash` for it by
+//! hashing each variant's discriminant. Explicit discriminants must be
+//! integer literals. Keys are written as `Enum::Variant` paths, and the enum
+//! must be defined in the same crate as the map and before it, since the
+//! plugin learns the discriminants from the attribute. Other paths, such as
+//! constants, are rejected:
+//!
+//! ```ignore
+//! #[phf_enum]
+//! #[deriving(PartialEq, Eq)]
+//! enum Color {
+//!     Red,
+//!     Green,
+//!     Blue,
+//! }
+//!
+//! static NAMES: phf::Map<Color, &'static str> = phf_map! {
+//!     Color::Red => "red",
+//!     Color::Green => "green",
+//!     Color::Blue => "blue",
+//! };
+//! ```
+//!
+//! The `#[phf_from_str]` attribute implements `FromStr` for a fieldless enum
+//! using a `phf::Map` from names to variants. A variant is parsed from its
+//! name unless a different one is given with `#[phf(name = "...")]`, and
+//! `#[phf(alias = "...")]` adds further names it is parsed from. The enum
+//! also gains an `as_str` method returning a variant's name, and a `names`
+//! function returning the map itself. This is an attribute rather than a
+//! `deriving` mode because plugins can't register new `deriving` traits. The
+//! enum doesn't need to be `Copy` or `Clone`:
+//!
+//! ```ignore
+//! #[phf_from_str]
+//! enum Keyword {
+//!     #[phf(name = "loop", alias = "lp")]
+//!     Loop,
+//!     #[phf(name = "continue")]
+//!     Continue,
+//! }
+//!
+//! assert_eq!(Some(Keyword::Loop), from_str("lp"));
+//! assert_eq!("continue", Keyword::Continue.as_str());
+//! ```
 #![doc(html_root_url="http://sfackler.github.io/doc")]
 #![feature(plugin_registrar, quote, default_type_params, macro_rules)]
 #![feature(slicing_syntax)]
 #![allow(unknown_features)]
 
-extern crate rand;
 extern crate syntax;
 extern crate time;
 extern crate rustc;
+extern crate serialize;
+extern crate toml;
 
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::collections::hash_map::{Occupied, Vacant};
-use syntax::ast::{mod, TokenTree, LitStr, LitBinary, LitByte, LitChar, Expr, ExprLit};
-use syntax::codemap::Span;
+use std::os;
+use syntax::ast::{mod, TokenTree, LitStr, LitBinary, LitByte, LitChar, Expr, ExprLit, ExprTup};
+use syntax::ast::{ExprVec, ExprCall, ExprPath};
+use syntax::attr::AttrMetaMethods;
+use syntax::codemap::{mod, Span};
+use syntax::ext::build::AstBuilder;
 use syntax::ext::base::{DummyResult,
+                        Decorator,
                         ExtCtxt,
                         MacResult};
 use syntax::fold::Folder;
 use syntax::parse;
-use syntax::parse::token::{InternedString, Comma, Eof, FatArrow};
+use syntax::parse::parser::Parser;
+use syntax::parse::token::{mod, InternedString, Comma, Eof, FatArrow};
 use syntax::print::pprust;
+use syntax::ptr::P;
 use rustc::plugin::Registry;
 
-use util::{Entry, Key};
+use shared::{ascii_lower, fold_str};
+use util::{Entry, Key, Hasher, Options, DEFAULT_PARAMS};
 use util::{generate_hash, create_map, create_set, create_ordered_map, create_ordered_set};
+use util::{create_bimap, create_multimap, create_prefix_map, create_sorted_map};
+use util::{RangeEntry, create_range_map};
+
+pub use shared::{PhfHash, PhfHasher, PhfBorrow, NoCase, FoldCase};
+pub use shared::{XxHasher, Sip13Hasher, FxHasher, MulShiftHasher};
 
 #[path="../../shared/mod.rs"]
 mod shared;
 pub mod util;
+mod file;
+
+// The discriminants of the variants of each enum marked with `#[phf_enum]`
+// so far, by enum and variant name. An enum name which has been marked more
+// than once maps to `None`, as paths naming it would be ambiguous.
+thread_local!(static PHF_ENUMS: RefCell<HashMap<String, Option<HashMap<String, i64>>>> =
+              RefCell::new(HashMap::new()))
 
 #[plugin_registrar]
 #[doc(hidden)]
@@ -38,11 +176,20 @@ pub fn macro_registrar(reg: &mut Registry) {
     reg.register_macro("phf_set", expand_phf_set);
     reg.register_macro("phf_ordered_map", expand_phf_ordered_map);
     reg.register_macro("phf_ordered_set", expand_phf_ordered_set);
+    reg.register_macro("phf_map_from_file", expand_phf_map_from_file);
+    reg.register_macro("phf_bimap", expand_phf_bimap);
+    reg.register_macro("phf_multimap", expand_phf_multimap);
+    reg.register_macro("phf_prefix_map", expand_phf_prefix_map);
+    reg.register_macro("phf_sorted_map", expand_phf_sorted_map);
+    reg.register_macro("phf_range_map", expand_phf_range_map);
+    reg.register_syntax_extension(token::intern("phf_enum"), Decorator(box expand_phf_enum));
+    reg.register_syntax_extension(token::intern("phf_from_str"),
+                                  Decorator(box expand_phf_from_str));
 }
 
 fn expand_phf_map(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree]) -> Box<MacResult+'static> {
-    let entries = match parse_map(cx, tts) {
-        Some(entries) => entries,
+    let (options, entries) = match parse_map(cx, tts) {
+        Some(result) => result,
         None => return DummyResult::expr(sp)
     };
 
@@ -50,14 +197,17 @@ fn expand_phf_map(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree]) -> Box<MacResul
         return DummyResult::expr(sp);
     }
 
-    let state = generate_hash(cx, sp, entries[]);
+    let state = match generate_hash(cx, sp, entries[], &options) {
+        Some(state) => state,
+        None => return DummyResult::expr(sp),
+    };
 
-    create_map(cx, sp, entries, state)
+    create_map(cx, sp, entries, state, &options)
 }
 
 fn expand_phf_set(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree]) -> Box<MacResult+'static> {
-    let entries = match parse_set(cx, tts) {
-        Some(entries) => entries,
+    let (options, entries) = match parse_set(cx, tts) {
+        Some(result) => result,
         None => return DummyResult::expr(sp)
     };
 
@@ -65,14 +215,17 @@ fn expand_phf_set(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree]) -> Box<MacResul
         return DummyResult::expr(sp);
     }
 
-    let state = generate_hash(cx, sp, entries[]);
+    let state = match generate_hash(cx, sp, entries[], &options) {
+        Some(state) => state,
+        None => return DummyResult::expr(sp),
+    };
 
-    create_set(cx, sp, entries, state)
+    create_set(cx, sp, entries, state, &options)
 }
 
 fn expand_phf_ordered_map(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree]) -> Box<MacResult+'static> {
-    let entries = match parse_map(cx, tts) {
-        Some(entries) => entries,
+    let (options, entries) = match parse_map(cx, tts) {
+        Some(result) => result,
         None => return DummyResult::expr(sp),
     };
 
@@ -80,14 +233,17 @@ fn expand_phf_ordered_map(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree]) -> Box<
         return DummyResult::expr(sp);
     }
 
-    let state = generate_hash(cx, sp, entries[]);
+    let state = match generate_hash(cx, sp, entries[], &options) {
+        Some(state) => state,
+        None => return DummyResult::expr(sp),
+    };
 
-    create_ordered_map(cx, sp, entries, state)
+    create_ordered_map(cx, sp, entries, state, &options)
 }
 
 fn expand_phf_ordered_set(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree]) -> Box<MacResult+'static> {
-    let entries = match parse_set(cx, tts) {
-        Some(entries) => entries,
+    let (options, entries) = match parse_set(cx, tts) {
+        Some(result) => result,
         None => return DummyResult::expr(sp)
     };
 
@@ -95,15 +251,669 @@ fn expand_phf_ordered_set(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree]) -> Box<
         return DummyResult::expr(sp);
     }
 
-    let state = generate_hash(cx, sp, entries[]);
+    let state = match generate_hash(cx, sp, entries[], &options) {
+        Some(state) => state,
+        None => return DummyResult::expr(sp),
+    };
+
+    create_ordered_set(cx, sp, entries, state, &options)
+}
+
+fn expand_phf_map_from_file(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree])
+                            -> Box<MacResult+'static> {
+    let (options, entries) = match parse_file_args(cx, sp, tts) {
+        Some(result) => result,
+        None => return DummyResult::expr(sp),
+    };
+
+    if has_duplicates(cx, sp, entries[]) {
+        return DummyResult::expr(sp);
+    }
+
+    let state = match generate_hash(cx, sp, entries[], &options) {
+        Some(state) => state,
+        None => return DummyResult::expr(sp),
+    };
+
+    create_map(cx, sp, entries, state, &options)
+}
+
+/// Returns the definition of `item` if it's a non-generic enum whose
+/// variants have no fields, reporting an error for the attribute `attr`
+/// otherwise.
+fn fieldless_enum<'a>(cx: &mut ExtCtxt, sp: Span, item: &'a ast::Item, attr: &str)
+                      -> Option<&'a ast::EnumDef> {
+    let def = match item.node {
+        ast::ItemEnum(ref def, ref generics) if !generics.is_parameterized() => def,
+        _ => {
+            cx.span_err(sp, format!("#[{}] may only be applied to non-generic enums", attr)[]);
+            return None;
+        }
+    };
+
+    for variant in def.variants.iter() {
+        match variant.node.kind {
+            ast::TupleVariantKind(ref args) if args.is_empty() => {}
+            _ => {
+                cx.span_err(variant.span, format!("#[{}] variants may not have fields", attr)[]);
+                return None;
+            }
+        }
+    }
+
+    Some(def)
+}
+
+fn expand_phf_bimap(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree]) -> Box<MacResult+'static> {
+    let (options, mut entries) = match parse_map(cx, tts) {
+        Some(result) => result,
+        None => return DummyResult::expr(sp),
+    };
+
+    // The values are the keys of the right side, so they must be literals as
+    // well.
+    let mut rights = vec![];
+    let mut bad = false;
+    for entry in entries.iter_mut() {
+        entry.value = cx.expander().fold_expr(entry.value.clone());
+        let key_contents = parse_key(cx, &*entry.value).unwrap_or_else(|| {
+            bad = true;
+            Key::Str(InternedString::new(""))
+        });
+        rights.push(Entry {
+            key_contents: key_contents,
+            key: entry.value.clone(),
+            value: entry.key.clone(),
+        });
+    }
+
+    if bad {
+        return DummyResult::expr(sp);
+    }
+
+    let left_dups = has_duplicates(cx, sp, entries[]);
+    if has_duplicates(cx, sp, rights[]) || left_dups {
+        return DummyResult::expr(sp);
+    }
+
+    let left = match generate_hash(cx, sp, entries[], &options) {
+        Some(state) => state,
+        None => return DummyResult::expr(sp),
+    };
+    let right = match generate_hash(cx, sp, rights[], &options) {
+        Some(state) => state,
+        None => return DummyResult::expr(sp),
+    };
+
+    create_bimap(cx, sp, entries, left, right, &options)
+}
+
+fn expand_phf_multimap(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree])
+                       -> Box<MacResult+'static> {
+    let (options, entries) = match parse_map(cx, tts) {
+        Some(result) => result,
+        None => return DummyResult::expr(sp),
+    };
+
+    // Group the values by key, with keys in the order of their first
+    // occurrence.
+    let mut keys: Vec<Entry> = vec![];
+    let mut values: Vec<Vec<P<Expr>>> = vec![];
+    let mut groups = HashMap::new();
+    for entry in entries.into_iter() {
+        match groups.entry(entry.key_contents.clone()) {
+            Occupied(e) => values[*e.get()].push(entry.value),
+            Vacant(e) => {
+                e.set(keys.len());
+                values.push(vec![entry.value]);
+                keys.push(Entry {
+                    key_contents: entry.key_contents,
+                    key: entry.key,
+                    value: quote_expr!(&*cx, ()),
+                });
+            }
+        }
+    }
+
+    let state = match generate_hash(cx, sp, keys[], &options) {
+        Some(state) => state,
+        None => return DummyResult::expr(sp),
+    };
+
+    create_multimap(cx, sp, keys, values, state, &options)
+}
+
+fn expand_phf_prefix_map(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree])
+                         -> Box<MacResult+'static> {
+    let (options, entries) = match parse_map(cx, tts) {
+        Some(result) => result,
+        None => return DummyResult::expr(sp),
+    };
+
+    let mut bad = false;
+    for entry in entries.iter() {
+        match entry.key_contents {
+            Key::Str(_) => {}
+            _ => {
+                cx.span_err(entry.key.span, "phf_prefix_map! keys must be string literals");
+                bad = true;
+            }
+        }
+    }
+
+    if bad || has_duplicates(cx, sp, entries[]) {
+        return DummyResult::expr(sp);
+    }
+
+    let state = match generate_hash(cx, sp, entries[], &options) {
+        Some(state) => state,
+        None => return DummyResult::expr(sp),
+    };
+
+    create_prefix_map(cx, sp, entries, state, &options)
+}
+
+fn expand_phf_sorted_map(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree])
+                         -> Box<MacResult+'static> {
+    let (options, mut entries) = match parse_map(cx, tts) {
+        Some(result) => result,
+        None => return DummyResult::expr(sp),
+    };
+
+    if has_duplicates(cx, sp, entries[]) {
+        return DummyResult::expr(sp);
+    }
+
+    let mut unordered = false;
+    entries.sort_by(|a, b| {
+        match a.key_contents.runtime_cmp(&b.key_contents) {
+            Some(ordering) => ordering,
+            None => {
+                unordered = true;
+                Equal
+            }
+        }
+    });
+    if unordered {
+        cx.span_err(sp, "phf_sorted_map! keys must be strings, byte strings, chars, bools or \
+                         integers of a single type, or tuples or arrays of those");
+        return DummyResult::expr(sp);
+    }
+
+    let state = match generate_hash(cx, sp, entries[], &options) {
+        Some(state) => state,
+        None => return DummyResult::expr(sp),
+    };
+
+    create_sorted_map(cx, sp, entries, state, &options)
+}
+
+fn expand_phf_range_map(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree])
+                        -> Box<MacResult+'static> {
+    let mut entries = match parse_range_map(cx, tts) {
+        Some(entries) => entries,
+        None => return DummyResult::expr(sp),
+    };
+
+    let mut unordered = false;
+    entries.sort_by(|a, b| {
+        match a.start.runtime_cmp(&b.start) {
+            Some(ordering) => ordering,
+            None => {
+                unordered = true;
+                Equal
+            }
+        }
+    });
+    if unordered {
+        cx.span_err(sp, "phf_range_map! bounds must be integers of a single type");
+        return DummyResult::expr(sp);
+    }
+
+    if has_overlaps(cx, sp, entries[]) {
+        return DummyResult::expr(sp);
+    }
+
+    create_range_map(cx, sp, entries)
+}
+
+fn expand_phf_enum(cx: &mut ExtCtxt, sp: Span, _: &ast::MetaItem, item: &ast::Item,
+                   push: |P<ast::Item>|) {
+    let def = match fieldless_enum(cx, sp, item, "phf_enum") {
+        Some(def) => def,
+        None => return,
+    };
+
+    let name = item.ident;
+    let mut variants = HashMap::new();
+    let mut arms = vec![];
+    let mut next = 0;
+    for variant in def.variants.iter() {
+        let discriminant = match variant.node.disr_expr {
+            Some(ref e) => match parse_discriminant(&**e) {
+                Some(discriminant) => discriminant,
+                None => {
+                    cx.span_err(e.span, "#[phf_enum] discriminants must be integer literals");
+                    return;
+                }
+            },
+            None => next,
+        };
+        next = discriminant + 1;
+        variants.insert(token::get_ident(variant.node.name).get().to_string(), discriminant);
+
+        let path = cx.path(variant.span, vec![name, variant.node.name]);
+        let pat = cx.pat_enum(variant.span, path, vec![]);
+        arms.push(cx.arm(variant.span, vec![pat], i64_literal(cx, variant.span, discriminant)));
+    }
+    let discriminant = cx.expr_match(sp, quote_expr!(cx, *self), arms);
+
+    let enum_name = token::get_ident(name).get().to_string();
+    PHF_ENUMS.with(|enums| {
+        match enums.borrow_mut().entry(enum_name.clone()) {
+            Occupied(mut e) => { e.set(None); }
+            Vacant(e) => { e.set(Some(variants.clone())); }
+        }
+    });
+
+    // This must hash a variant the same way as `Key::Variant`. Matching
+    // rather than casting `*self` avoids requiring the enum to be `Copy`.
+    let item = quote_item!(cx,
+        impl ::phf::PhfHash for $name {
+            fn phf_hash_into<W>(&self, state: &mut W) where W: ::std::hash::Writer {
+                use ::phf::PhfHash;
+                let discriminant: i64 = $discriminant;
+                discriminant.phf_hash_into(state)
+            }
+        }
+    );
+    push(item.unwrap());
+}
+
+/// Returns the value of an enum discriminant, if it's an integer literal.
+fn parse_discriminant(e: &Expr) -> Option<i64> {
+    match e.node {
+        ExprLit(ref lit) => match lit.node {
+            ast::LitInt(i, ast::SignedIntLit(_, ast::Minus)) => Some(-(i as i64)),
+            ast::LitInt(i, _) => Some(i as i64),
+            _ => None,
+        },
+        ast::ExprUnary(ast::UnNeg, ref inner) => parse_discriminant(&**inner).map(|i| -i),
+        _ => None,
+    }
+}
+
+fn i64_literal(cx: &mut ExtCtxt, sp: Span, i: i64) -> P<Expr> {
+    let (value, sign) = if i < 0 { (-i as u64, ast::Minus) } else { (i as u64, ast::Plus) };
+    cx.expr_lit(sp, ast::LitInt(value, ast::SignedIntLit(ast::TyI64, sign)))
+}
+
+fn expand_phf_from_str(cx: &mut ExtCtxt, sp: Span, _: &ast::MetaItem, item: &ast::Item,
+                       push: |P<ast::Item>|) {
+    let def = match fieldless_enum(cx, sp, item, "phf_from_str") {
+        Some(def) => def,
+        None => return,
+    };
+
+    let name = item.ident;
+    let mut entries = vec![];
+    let mut arms = vec![];
+    let mut variant_arms = vec![];
+    let mut bad = false;
+    for variant in def.variants.iter() {
+        let names = match variant_names(cx, &**variant) {
+            Some(names) => names,
+            None => {
+                bad = true;
+                continue;
+            }
+        };
+
+        let path = cx.path(variant.span, vec![name, variant.node.name]);
+        let pat = cx.pat_enum(variant.span, path.clone(), vec![]);
+        let primary = cx.expr_str(variant.span, names[0].clone());
+        arms.push(cx.arm(variant.span, vec![pat.clone()], primary));
+        variant_arms.push(cx.arm(variant.span, vec![pat], cx.expr_path(path.clone())));
+
+        for s in names.into_iter() {
+            entries.push(Entry {
+                key_contents: Key::Str(s.clone()),
+                key: cx.expr_str(variant.span, s),
+                value: cx.expr_path(path.clone()),
+            });
+        }
+    }
+
+    if bad || has_duplicates(cx, sp, entries[]) {
+        return;
+    }
+
+    let options = Options {
+        params: DEFAULT_PARAMS,
+        hasher: Hasher::Xx,
+    };
+    let state = match generate_hash(cx, sp, entries[], &options) {
+        Some(state) => state,
+        None => return,
+    };
+    let map = create_map(cx, sp, entries, state, &options).make_expr().unwrap();
+    let as_str = cx.expr_match(sp, quote_expr!(cx, *self), arms);
+    // Rebuilds the variant from the map's value, since it can't be copied out.
+    let variant = cx.expr_match(sp, quote_expr!(cx, *v), variant_arms);
+
+    let item = quote_item!(cx,
+        impl $name {
+            /// Returns the name this variant is parsed from.
+            pub fn as_str(&self) -> &'static str {
+                $as_str
+            }
+
+            /// Returns the map from the names and aliases of the variants to
+            /// the variants.
+            pub fn names() -> &'static ::phf::Map<&'static str, $name> {
+                static NAMES: ::phf::Map<&'static str, $name> = $map;
+                &NAMES
+            }
+        }
+    );
+    push(item.unwrap());
+
+    let item = quote_item!(cx,
+        impl ::std::str::FromStr for $name {
+            fn from_str(s: &str) -> Option<$name> {
+                $name::names().get(s).map(|v| $variant)
+            }
+        }
+    );
+    push(item.unwrap());
+}
+
+/// Returns the names a `#[phf_from_str]` variant is parsed from, as given by
+/// its `#[phf(...)]` attributes.
+///
+/// The first name is the one returned by `as_str`.
+fn variant_names(cx: &mut ExtCtxt, variant: &ast::Variant) -> Option<Vec<InternedString>> {
+    let mut name = None;
+    let mut aliases = vec![];
+    let mut bad = false;
+    for attr in variant.node.attrs.iter() {
+        if !attr.check_name("phf") {
+            continue;
+        }
+
+        let items = match attr.meta_item_list() {
+            Some(items) => items,
+            None => {
+                cx.span_err(attr.span, "expected #[phf(name = \"...\", alias = \"...\")]");
+                bad = true;
+                continue;
+            }
+        };
+
+        for item in items.iter() {
+            match item.node {
+                ast::MetaNameValue(ref key, ref lit) => match (key.get(), &lit.node) {
+                    ("name", &ast::LitStr(ref s, _)) if name.is_none() => name = Some(s.clone()),
+                    ("name", &ast::LitStr(..)) => {
+                        cx.span_err(item.span, "duplicate `name`");
+                        bad = true;
+                    }
+                    ("alias", &ast::LitStr(ref s, _)) => aliases.push(s.clone()),
+                    ("name", _) | ("alias", _) => {
+                        cx.span_err(lit.span, "expected a string literal");
+                        bad = true;
+                    }
+                    (key, _) => {
+                        cx.span_err(item.span, format!("unknown key `{}`", key)[]);
+                        bad = true;
+                    }
+                },
+                _ => {
+                    cx.span_err(item.span, "expected `name = \"...\"` or `alias = \"...\"`");
+                    bad = true;
+                }
+            }
+        }
+    }
+
+    if bad {
+        return None;
+    }
 
-    create_ordered_set(cx, sp, entries, state)
+    let mut names = vec![name.unwrap_or_else(|| token::get_ident(variant.node.name))];
+    names.extend(aliases.into_iter());
+    Some(names)
 }
 
-fn parse_map(cx: &mut ExtCtxt, tts: &[TokenTree]) -> Option<Vec<Entry>> {
+fn parse_options(cx: &mut ExtCtxt, parser: &mut Parser) -> Option<Options> {
+    let mut options = Options {
+        params: DEFAULT_PARAMS,
+        hasher: Hasher::Xx,
+    };
+
+    match os::getenv("PHF_MAX_ATTEMPTS") {
+        Some(s) => match from_str(s[]) {
+            Some(max_attempts) => options.params.max_attempts = max_attempts,
+            None => {
+                cx.span_err(parser.span, format!("invalid PHF_MAX_ATTEMPTS value `{}`", s)[]);
+                return None;
+            }
+        },
+        None => {}
+    }
+
+    if !parser.eat(&token::Pound) {
+        return Some(options);
+    }
+
+    parser.expect(&token::OpenDelim(token::Bracket));
+    while parser.token != token::CloseDelim(token::Bracket) {
+        let span = parser.span;
+        let name = parser.parse_ident();
+        parser.expect(&token::Eq);
+        let lit = parser.parse_lit();
+
+        match (token::get_ident(name).get(), &lit.node) {
+            ("lambda", &ast::LitInt(n, _)) if n > 0 => options.params.lambda = n as uint,
+            ("max_attempts", &ast::LitInt(n, _)) if n > 0 => {
+                options.params.max_attempts = n as uint
+            }
+            ("lambda", _) | ("max_attempts", _) => {
+                cx.span_err(lit.span, "expected a positive integer");
+                return None;
+            }
+            ("load_factor", &ast::LitFloat(ref f, _))
+                    | ("load_factor", &ast::LitFloatUnsuffixed(ref f)) => {
+                match from_str::<f64>(f.get()) {
+                    Some(f) if f > 0. && f <= 1. => options.params.load_factor = f,
+                    _ => {
+                        cx.span_err(lit.span, "expected a load factor greater than 0 and \
+                                               at most 1");
+                        return None;
+                    }
+                }
+            }
+            ("load_factor", _) => {
+                cx.span_err(lit.span, "expected a float literal");
+                return None;
+            }
+            ("hasher", &ast::LitStr(ref s, _)) => {
+                match Hasher::from_name(s.get()) {
+                    Some(hasher) => options.hasher = hasher,
+                    None => {
+                        cx.span_err(lit.span, format!("unknown hasher `{}`", s)[]);
+                        return None;
+                    }
+                }
+            }
+            ("hasher", _) => {
+                cx.span_err(lit.span, "expected a string literal");
+                return None;
+            }
+            (name, _) => {
+                cx.span_err(span, format!("unknown option `{}`", name)[]);
+                return None;
+            }
+        }
+
+        if !parser.eat(&Comma) && parser.token != token::CloseDelim(token::Bracket) {
+            cx.span_err(parser.span, "expected `,`");
+            return None;
+        }
+    }
+    parser.expect(&token::CloseDelim(token::Bracket));
+
+    Some(options)
+}
+
+fn parse_file_args(cx: &mut ExtCtxt, sp: Span, tts: &[TokenTree])
+                   -> Option<(Options, Vec<Entry>)> {
+    let mut parser = parse::new_parser_from_tts(cx.parse_sess(), cx.cfg(), tts.to_vec());
+    let options = match parse_options(cx, &mut parser) {
+        Some(options) => options,
+        None => return None,
+    };
+
+    let name = cx.expander().fold_expr(parser.parse_expr());
+    let path = match name.node {
+        ExprLit(ref lit) => match lit.node {
+            LitStr(ref s, _) => Some(Path::new(s.get())),
+            _ => None,
+        },
+        _ => None,
+    };
+    let path = match path {
+        Some(path) => path,
+        None => {
+            cx.span_err(name.span, "expected a file name string literal");
+            return None;
+        }
+    };
+
+    parser.expect(&Comma);
+    let key_ty = parser.parse_ty(false);
+    parser.expect(&Comma);
+    let value_ty = parser.parse_ty(false);
+    parser.eat(&Comma);
+    if parser.token != Eof {
+        cx.span_err(parser.span, "expected end of macro invocation");
+        return None;
+    }
+
+    file::parse_entries(cx, sp, &path, &*key_ty, &*value_ty).map(|entries| (options, entries))
+}
+
+fn parse_range_map(cx: &mut ExtCtxt, tts: &[TokenTree]) -> Option<Vec<RangeEntry>> {
     let mut parser = parse::new_parser_from_tts(cx.parse_sess(), cx.cfg(), tts.to_vec());
     let mut entries = Vec::new();
 
+    let mut bad = false;
+    while parser.token != Eof {
+        let lo = parser.span.lo;
+        // The bounds are parsed as prefix expressions so that the parser
+        // stops at the `..` between them.
+        let start = cx.expander().fold_expr(parser.parse_prefix_expr());
+        let inclusive = if parser.eat(&token::DotDotDot) {
+            true
+        } else if parser.eat(&token::DotDot) {
+            false
+        } else {
+            cx.span_err(parser.span, "expected `..` or `...`");
+            return None;
+        };
+        let end = cx.expander().fold_expr(parser.parse_prefix_expr());
+        let span = codemap::mk_sp(lo, parser.last_span.hi);
+
+        if !parser.eat(&FatArrow) {
+            cx.span_err(parser.span, "expected `=>`");
+            return None;
+        }
+
+        let value = parser.parse_expr();
+
+        let bounds = match (parse_bound(cx, &*start), parse_bound(cx, &*end)) {
+            (Some(start), Some(end)) if start.runtime_cmp(&end).is_none() => {
+                cx.span_err(span, "range bounds must have the same type");
+                None
+            }
+            (Some(start), Some(end)) => {
+                let end = if inclusive { Some(end) } else { end.pred() };
+                match end {
+                    Some(ref end) if start.runtime_cmp(end) != Some(Greater) => {
+                        Some((start, end.clone()))
+                    }
+                    _ => {
+                        cx.span_err(span, "empty range");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        match bounds {
+            Some((start, end)) => {
+                entries.push(RangeEntry {
+                    start: start,
+                    end: end,
+                    span: span,
+                    value: value,
+                });
+            }
+            None => bad = true,
+        }
+
+        if !parser.eat(&Comma) && parser.token != Eof {
+            cx.span_err(parser.span, "expected `,`");
+            return None;
+        }
+    }
+
+    if bad {
+        return None;
+    }
+
+    Some(entries)
+}
+
+/// Parses a range bound, which must be an integer literal, possibly negated.
+fn parse_bound(cx: &mut ExtCtxt, e: &Expr) -> Option<Key> {
+    let (negated, lit) = match e.node {
+        ast::ExprUnary(ast::UnNeg, ref inner) => (true, &**inner),
+        _ => (false, e),
+    };
+
+    let key = match lit.node {
+        ExprLit(_) => match parse_key(cx, lit) {
+            Some(key) => key,
+            None => return None,
+        },
+        _ => {
+            cx.span_err(e.span, "expected an integer literal");
+            return None;
+        }
+    };
+    let key = if negated {
+        key.neg()
+    } else if key.is_int() {
+        Some(key)
+    } else {
+        None
+    };
+    if key.is_none() {
+        cx.span_err(e.span, "expected an integer literal");
+    }
+    key
+}
+
+fn parse_map(cx: &mut ExtCtxt, tts: &[TokenTree]) -> Option<(Options, Vec<Entry>)> {
+    let mut parser = parse::new_parser_from_tts(cx.parse_sess(), cx.cfg(), tts.to_vec());
+    let options = match parse_options(cx, &mut parser) {
+        Some(options) => options,
+        None => return None,
+    };
+    let mut entries = Vec::new();
+
     let mut bad = false;
     while parser.token != Eof {
         let key = cx.expander().fold_expr(parser.parse_expr());
@@ -135,11 +945,15 @@ fn parse_map(cx: &mut ExtCtxt, tts: &[TokenTree]) -> Option<Vec<Entry>> {
         return None;
     }
 
-    Some(entries)
+    Some((options, entries))
 }
 
-fn parse_set(cx: &mut ExtCtxt, tts: &[TokenTree]) -> Option<Vec<Entry>> {
+fn parse_set(cx: &mut ExtCtxt, tts: &[TokenTree]) -> Option<(Options, Vec<Entry>)> {
     let mut parser = parse::new_parser_from_tts(cx.parse_sess(), cx.cfg(), tts.to_vec());
+    let options = match parse_options(cx, &mut parser) {
+        Some(options) => options,
+        None => return None,
+    };
     let mut entries = Vec::new();
     let value = quote_expr!(&*cx, ());
 
@@ -167,7 +981,7 @@ fn parse_set(cx: &mut ExtCtxt, tts: &[TokenTree]) -> Option<Vec<Entry>> {
         return None;
     }
 
-    Some(entries)
+    Some((options, entries))
 }
 
 fn parse_key(cx: &mut ExtCtxt, e: &Expr) -> Option<Key> {
@@ -186,10 +1000,13 @@ fn parse_key(cx: &mut ExtCtxt, e: &Expr) -> Option<Key> {
                 ast::LitInt(i, ast::SignedIntLit(ast::TyI32, ast::Minus)) => Some(Key::I32(-(i as i32))),
                 ast::LitInt(i, ast::SignedIntLit(ast::TyI64, ast::Plus)) => Some(Key::I64(i as i64)),
                 ast::LitInt(i, ast::SignedIntLit(ast::TyI64, ast::Minus)) => Some(Key::I64(-(i as i64))),
+                ast::LitInt(i, ast::SignedIntLit(ast::TyI, ast::Plus)) => Some(Key::Int(i as i64)),
+                ast::LitInt(i, ast::SignedIntLit(ast::TyI, ast::Minus)) => Some(Key::Int(-(i as i64))),
                 ast::LitInt(i, ast::UnsignedIntLit(ast::TyU8)) => Some(Key::U8(i as u8)),
                 ast::LitInt(i, ast::UnsignedIntLit(ast::TyU16)) => Some(Key::U16(i as u16)),
                 ast::LitInt(i, ast::UnsignedIntLit(ast::TyU32)) => Some(Key::U32(i as u32)),
                 ast::LitInt(i, ast::UnsignedIntLit(ast::TyU64)) => Some(Key::U64(i as u64)),
+                ast::LitInt(i, ast::UnsignedIntLit(ast::TyU)) => Some(Key::Uint(i as u64)),
                 ast::LitBool(b) => Some(Key::Bool(b)),
                 _ => {
                     cx.span_err(e.span, "unsupported literal type");
@@ -197,6 +1014,55 @@ fn parse_key(cx: &mut ExtCtxt, e: &Expr) -> Option<Key> {
                 }
             }
         }
+        ExprCall(ref f, ref args) => {
+            let name = match f.node {
+                ExprPath(ref path) => token::get_ident(path.segments.last().unwrap().identifier),
+                _ => {
+                    cx.span_err(e.span, "expected a literal");
+                    return None;
+                }
+            };
+            let s = if args.len() == 1 {
+                match args[0].node {
+                    ExprLit(ref lit) => match lit.node {
+                        ast::LitStr(ref s, _) => Some(s.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            } else {
+                None
+            };
+            let s = match s {
+                Some(s) => s,
+                None => {
+                    cx.span_err(e.span, "expected a single string literal argument");
+                    return None;
+                }
+            };
+            match name.get() {
+                "NoCase" => {
+                    let folded = s.get().bytes().map(ascii_lower).collect();
+                    Some(Key::NoCase(String::from_utf8(folded).unwrap()))
+                }
+                "FoldCase" => Some(Key::FoldCase(fold_str(s.get()).collect())),
+                _ => {
+                    cx.span_err(f.span, format!("unsupported key wrapper `{}`", name)[]);
+                    None
+                }
+            }
+        }
+        ExprPath(ref path) => variant_discriminant(cx, e.span, path).map(Key::Variant),
+        ExprTup(ref elems) | ExprVec(ref elems) => {
+            let mut keys = vec![];
+            for elem in elems.iter() {
+                match parse_key(cx, &**elem) {
+                    Some(key) => keys.push(key),
+                    None => return None,
+                }
+            }
+            Some(Key::Compound(keys))
+        }
         _ => {
             cx.span_err(e.span, "expected a literal");
             None
@@ -204,6 +1070,43 @@ fn parse_key(cx: &mut ExtCtxt, e: &Expr) -> Option<Key> {
     }
 }
 
+/// Returns the discriminant of the variant `path` names, if it's a path to a
+/// variant of an enum marked with `#[phf_enum]`, reporting an error
+/// otherwise.
+fn variant_discriminant(cx: &mut ExtCtxt, sp: Span, path: &ast::Path) -> Option<i64> {
+    let len = path.segments.len();
+    if len < 2 {
+        cx.span_err(sp, "expected a literal");
+        return None;
+    }
+    let enum_name = token::get_ident(path.segments[len - 2].identifier).get().to_string();
+    let variant_name = token::get_ident(path.segments[len - 1].identifier).get().to_string();
+
+    let variants = PHF_ENUMS.with(|enums| enums.borrow().get(&enum_name).map(|v| v.clone()));
+    match variants {
+        Some(Some(variants)) => match variants.get(&variant_name) {
+            Some(&discriminant) => Some(discriminant),
+            None => {
+                cx.span_err(sp, format!("enum `{}` has no variant `{}`",
+                                        enum_name, variant_name)[]);
+                None
+            }
+        },
+        Some(None) => {
+            cx.span_err(sp, format!("more than one enum named `{}` is marked with #[phf_enum]",
+                                    enum_name)[]);
+            None
+        }
+        None => {
+            cx.span_err(sp, "expected a literal");
+            cx.span_note(sp, format!("variants of `{}` may only be used as keys if it is an enum \
+                                      marked with #[phf_enum] earlier in the crate",
+                                     enum_name)[]);
+            None
+        }
+    }
+}
+
 fn has_duplicates(cx: &mut ExtCtxt, sp: Span, entries: &[Entry]) -> bool {
     let mut dups = false;
     let mut strings = HashMap::new();
@@ -229,3 +1132,21 @@ fn has_duplicates(cx: &mut ExtCtxt, sp: Span, entries: &[Entry]) -> bool {
 
     dups
 }
+
+fn has_overlaps(cx: &mut ExtCtxt, sp: Span, entries: &[RangeEntry]) -> bool {
+    let mut overlaps = false;
+    for pair in entries.windows(2) {
+        if pair[0].end.runtime_cmp(&pair[1].start) == Some(Less) {
+            continue;
+        }
+
+        overlaps = true;
+        cx.span_err(sp, format!("ranges `{}` and `{}` overlap",
+                                cx.codemap().span_to_snippet(pair[0].span).unwrap_or_default(),
+                                cx.codemap().span_to_snippet(pair[1].span).unwrap_or_default())[]);
+        cx.span_note(pair[0].span, "one range here");
+        cx.span_note(pair[1].span, "other range here");
+    }
+
+    overlaps
+}
@@ -2,22 +2,22 @@ use std::os;
 use std::rc::Rc;
 use std::hash;
 use std::hash::Hash;
+use std::num::Int;
 
-use syntax::ast::Expr;
+use syntax::ast::{mod, Expr};
 use syntax::codemap::Span;
 use syntax::ext::base::{ExtCtxt, MacResult, MacExpr};
 use syntax::ext::build::AstBuilder;
 use syntax::parse::token::InternedString;
+use syntax::print::pprust;
 use syntax::ptr::P;
-use rand::{Rng, SeedableRng, XorShiftRng};
 
-use shared::PhfHash;
+use shared::{PhfHash, NoCase, FoldCase, XxHasher, Sip13Hasher, FxHasher, MulShiftHasher};
 
 use time;
 
-const DEFAULT_LAMBDA: uint = 5;
-
-const FIXED_SEED: [u32, ..4] = [3141592653, 589793238, 462643383, 2795028841];
+pub use shared::{HashState, HashParams, DEFAULT_PARAMS, DEFAULT_MAX_ATTEMPTS};
+pub use shared::{generate_hash_keys, try_generate_hash, find_collisions, phf_hash};
 
 #[deriving(PartialEq, Eq, Clone)]
 pub enum Key {
@@ -32,7 +32,20 @@ pub enum Key {
     I32(i32),
     U64(u64),
     I64(i64),
+    // Pointer-sized integers are widened so that keys hash the same way
+    // regardless of the target's pointer width.
+    Uint(u64),
+    Int(i64),
     Bool(bool),
+    // Stored with ASCII case folded so that keys differing only in case are
+    // caught as duplicates.
+    NoCase(String),
+    // Stored with Unicode case folded by `fold_str`, like `NoCase`.
+    FoldCase(String),
+    // The discriminant of a variant of an enum marked with `#[phf_enum]`.
+    Variant(i64),
+    // The elements of a tuple or fixed-size array key.
+    Compound(Vec<Key>),
 }
 
 impl<S> Hash<S> for Key where S: hash::Writer {
@@ -49,180 +62,401 @@ impl<S> Hash<S> for Key where S: hash::Writer {
             Key::I32(b) => b.hash(state),
             Key::U64(b) => b.hash(state),
             Key::I64(b) => b.hash(state),
+            Key::Uint(b) => b.hash(state),
+            Key::Int(b) => b.hash(state),
             Key::Bool(b) => b.hash(state),
+            Key::NoCase(ref s) => s.hash(state),
+            Key::FoldCase(ref s) => s.hash(state),
+            Key::Variant(d) => d.hash(state),
+            Key::Compound(ref keys) => keys.hash(state),
+        }
+    }
+}
+
+impl Key {
+    /// Compares two keys as the corresponding values would be compared at
+    /// runtime.
+    ///
+    /// Returns `None` for keys of different kinds, and for case-insensitive
+    /// and enum variant keys, whose order isn't known.
+    pub fn runtime_cmp(&self, other: &Key) -> Option<Ordering> {
+        match (self, other) {
+            (&Key::Str(ref a), &Key::Str(ref b)) => a.get().partial_cmp(b.get()),
+            (&Key::Binary(ref a), &Key::Binary(ref b)) => a.partial_cmp(b),
+            (&Key::Char(a), &Key::Char(b)) => a.partial_cmp(&b),
+            (&Key::U8(a), &Key::U8(b)) => a.partial_cmp(&b),
+            (&Key::I8(a), &Key::I8(b)) => a.partial_cmp(&b),
+            (&Key::U16(a), &Key::U16(b)) => a.partial_cmp(&b),
+            (&Key::I16(a), &Key::I16(b)) => a.partial_cmp(&b),
+            (&Key::U32(a), &Key::U32(b)) => a.partial_cmp(&b),
+            (&Key::I32(a), &Key::I32(b)) => a.partial_cmp(&b),
+            (&Key::U64(a), &Key::U64(b)) => a.partial_cmp(&b),
+            (&Key::I64(a), &Key::I64(b)) => a.partial_cmp(&b),
+            (&Key::Uint(a), &Key::Uint(b)) => a.partial_cmp(&b),
+            (&Key::Int(a), &Key::Int(b)) => a.partial_cmp(&b),
+            (&Key::Bool(a), &Key::Bool(b)) => a.partial_cmp(&b),
+            (&Key::Compound(ref a), &Key::Compound(ref b)) => {
+                for (a, b) in a.iter().zip(b.iter()) {
+                    match a.runtime_cmp(b) {
+                        Some(Equal) => {}
+                        ordering => return ordering,
+                    }
+                }
+                a.len().partial_cmp(&b.len())
+            }
+            _ => None,
+        }
+    }
+
+    /// Determines if this is an integer key.
+    pub fn is_int(&self) -> bool {
+        match *self {
+            Key::U8(_) | Key::I8(_) | Key::U16(_) | Key::I16(_) | Key::U32(_) | Key::I32(_)
+                | Key::U64(_) | Key::I64(_) | Key::Uint(_) | Key::Int(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the negation of a signed integer key.
+    pub fn neg(&self) -> Option<Key> {
+        match *self {
+            Key::I8(v) => Some(Key::I8(-v)),
+            Key::I16(v) => Some(Key::I16(-v)),
+            Key::I32(v) => Some(Key::I32(-v)),
+            Key::I64(v) => Some(Key::I64(-v)),
+            Key::Int(v) => Some(Key::Int(-v)),
+            _ => None,
         }
     }
+
+    /// Returns the integer key one less than this one, if there is one.
+    pub fn pred(&self) -> Option<Key> {
+        match *self {
+            Key::U8(v) => v.checked_sub(1).map(Key::U8),
+            Key::I8(v) => v.checked_sub(1).map(Key::I8),
+            Key::U16(v) => v.checked_sub(1).map(Key::U16),
+            Key::I16(v) => v.checked_sub(1).map(Key::I16),
+            Key::U32(v) => v.checked_sub(1).map(Key::U32),
+            Key::I32(v) => v.checked_sub(1).map(Key::I32),
+            Key::U64(v) => v.checked_sub(1).map(Key::U64),
+            Key::I64(v) => v.checked_sub(1).map(Key::I64),
+            Key::Uint(v) => v.checked_sub(1).map(Key::Uint),
+            Key::Int(v) => v.checked_sub(1).map(Key::Int),
+            _ => None,
+        }
+    }
+
+    /// Returns a literal expression for an integer key.
+    pub fn to_int_expr(&self, cx: &ExtCtxt, sp: Span) -> Option<P<Expr>> {
+        let (v, ty) = match *self {
+            Key::U8(v) => (v as u64, ast::UnsignedIntLit(ast::TyU8)),
+            Key::U16(v) => (v as u64, ast::UnsignedIntLit(ast::TyU16)),
+            Key::U32(v) => (v as u64, ast::UnsignedIntLit(ast::TyU32)),
+            Key::U64(v) => (v, ast::UnsignedIntLit(ast::TyU64)),
+            Key::Uint(v) => (v, ast::UnsignedIntLit(ast::TyU)),
+            Key::I8(v) => return Some(signed_expr(cx, sp, v as i64, ast::TyI8)),
+            Key::I16(v) => return Some(signed_expr(cx, sp, v as i64, ast::TyI16)),
+            Key::I32(v) => return Some(signed_expr(cx, sp, v as i64, ast::TyI32)),
+            Key::I64(v) => return Some(signed_expr(cx, sp, v, ast::TyI64)),
+            Key::Int(v) => return Some(signed_expr(cx, sp, v, ast::TyI)),
+            _ => return None,
+        };
+        Some(cx.expr_lit(sp, ast::LitInt(v, ty)))
+    }
+}
+
+fn signed_expr(cx: &ExtCtxt, sp: Span, v: i64, ty: ast::IntTy) -> P<Expr> {
+    let lit = cx.expr_lit(sp, ast::LitInt(v.abs() as u64, ast::SignedIntLit(ty, ast::Plus)));
+    if v < 0 {
+        cx.expr_unary(sp, ast::UnNeg, lit)
+    } else {
+        lit
+    }
 }
 
 impl PhfHash for Key {
-    fn phf_hash(&self, key: u64) -> (u32, u32, u32) {
+    fn phf_hash_into<W>(&self, state: &mut W) where W: hash::Writer {
         match *self {
-            Key::Str(ref s) => s.get().phf_hash(key),
-            Key::Binary(ref b) => (**b)[].phf_hash(key),
-            Key::Char(c) => c.phf_hash(key),
-            Key::U8(b) => b.phf_hash(key),
-            Key::I8(b) => b.phf_hash(key),
-            Key::U16(b) => b.phf_hash(key),
-            Key::I16(b) => b.phf_hash(key),
-            Key::U32(b) => b.phf_hash(key),
-            Key::I32(b) => b.phf_hash(key),
-            Key::U64(b) => b.phf_hash(key),
-            Key::I64(b) => b.phf_hash(key),
-            Key::Bool(b) => b.phf_hash(key),
+            Key::Str(ref s) => s.get().phf_hash_into(state),
+            Key::Binary(ref b) => (**b)[].phf_hash_into(state),
+            Key::Char(c) => c.phf_hash_into(state),
+            Key::U8(b) => b.phf_hash_into(state),
+            Key::I8(b) => b.phf_hash_into(state),
+            Key::U16(b) => b.phf_hash_into(state),
+            Key::I16(b) => b.phf_hash_into(state),
+            Key::U32(b) => b.phf_hash_into(state),
+            Key::I32(b) => b.phf_hash_into(state),
+            Key::U64(b) => b.phf_hash_into(state),
+            Key::I64(b) => b.phf_hash_into(state),
+            Key::Uint(b) => b.phf_hash_into(state),
+            Key::Int(b) => b.phf_hash_into(state),
+            Key::Bool(b) => b.phf_hash_into(state),
+            Key::NoCase(ref s) => NoCase(s[]).phf_hash_into(state),
+            Key::FoldCase(ref s) => FoldCase(s[]).phf_hash_into(state),
+            Key::Variant(d) => d.phf_hash_into(state),
+            Key::Compound(ref keys) => {
+                for key in keys.iter() {
+                    key.phf_hash_into(state);
+                }
+            }
         }
     }
 }
 
+/// The hash function family used to build a table.
+#[deriving(Clone)]
+pub enum Hasher {
+    Xx,
+    Sip13,
+    Fx,
+    MulShift,
+}
+
+impl Hasher {
+    /// Looks up a hasher by the name used in the `hasher` macro option.
+    pub fn from_name(name: &str) -> Option<Hasher> {
+        match name {
+            "xx" => Some(Hasher::Xx),
+            "sip13" => Some(Hasher::Sip13),
+            "fx" => Some(Hasher::Fx),
+            "mul_shift" => Some(Hasher::MulShift),
+            _ => None,
+        }
+    }
+
+    /// Generates a hash for `keys` with this hasher.
+    pub fn generate_hash<K>(&self, keys: &[K], params: &HashParams) -> Option<HashState>
+            where K: PhfHash {
+        match *self {
+            Hasher::Xx => generate_hash_keys(keys, &XxHasher, params),
+            Hasher::Sip13 => generate_hash_keys(keys, &Sip13Hasher, params),
+            Hasher::Fx => generate_hash_keys(keys, &FxHasher, params),
+            Hasher::MulShift => generate_hash_keys(keys, &MulShiftHasher, params),
+        }
+    }
+
+    /// Returns the name of the hasher's type in the `phf` crate.
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Hasher::Xx => "XxHasher",
+            Hasher::Sip13 => "Sip13Hasher",
+            Hasher::Fx => "FxHasher",
+            Hasher::MulShift => "MulShiftHasher",
+        }
+    }
+
+    fn to_expr(&self, cx: &ExtCtxt) -> P<Expr> {
+        match *self {
+            Hasher::Xx => quote_expr!(cx, ::phf::XxHasher),
+            Hasher::Sip13 => quote_expr!(cx, ::phf::Sip13Hasher),
+            Hasher::Fx => quote_expr!(cx, ::phf::FxHasher),
+            Hasher::MulShift => quote_expr!(cx, ::phf::MulShiftHasher),
+        }
+    }
+}
+
+/// The options controlling how a table is built.
+#[deriving(Clone)]
+pub struct Options {
+    pub params: HashParams,
+    pub hasher: Hasher,
+}
+
 pub struct Entry {
     pub key_contents: Key,
     pub key: P<Expr>,
     pub value: P<Expr>
 }
 
-pub struct HashState {
-    key: u64,
-    disps: Vec<(u32, u32)>,
-    map: Vec<uint>,
-}
-
-pub fn generate_hash(cx: &mut ExtCtxt, sp: Span, entries: &[Entry]) -> HashState {
-    let mut rng: XorShiftRng = SeedableRng::from_seed(FIXED_SEED);
+pub fn generate_hash(cx: &mut ExtCtxt, sp: Span, entries: &[Entry], options: &Options)
+                     -> Option<HashState> {
+    let keys = entries.iter().map(|e| e.key_contents.clone()).collect::<Vec<_>>();
     let start = time::precise_time_s();
-    let state;
-    loop {
-        match try_generate_hash(entries, &mut rng) {
-            Some(s) => {
-                state = s;
-                break;
-            }
-            None => {}
-        }
-    }
+    let state = options.hasher.generate_hash(keys[], &options.params);
     let time = time::precise_time_s() - start;
     if os::getenv("PHF_STATS").is_some() {
         cx.span_note(sp, format!("PHF generation took {} seconds", time)[]);
     }
 
+    if state.is_none() {
+        report_failure(cx, sp, entries, keys[], &options.params);
+    }
+
     state
 }
 
-pub fn try_generate_hash(entries: &[Entry], rng: &mut XorShiftRng) -> Option<HashState> {
-    struct Bucket {
-        idx: uint,
-        keys: Vec<uint>,
+fn report_failure(cx: &mut ExtCtxt, sp: Span, entries: &[Entry], keys: &[Key],
+                  params: &HashParams) {
+    let collisions = find_collisions(keys);
+    if collisions.is_empty() {
+        cx.span_err(sp, format!("unable to generate a perfect hash after {} attempts",
+                                params.max_attempts)[]);
+        return;
     }
 
-    struct Hashes {
-        g: u32,
-        f1: u32,
-        f2: u32,
+    for group in collisions.iter() {
+        let names = group.iter().map(|&i| {
+            format!("`{}`", pprust::expr_to_string(&*entries[i].key))
+        }).collect::<Vec<_>>();
+        cx.span_err(sp, format!("keys {} have colliding hashes", names.connect(", "))[]);
+        for &i in group.iter() {
+            cx.span_note(entries[i].key.span, "one occurrence here");
+        }
     }
+}
 
-    let key = rng.gen();
-
-    let hashes: Vec<_> = entries.iter().map(|entry| {
-        let (g, f1, f2) = entry.key_contents.phf_hash(key);
-        Hashes {
-            g: g,
-            f1: f1,
-            f2: f2
-        }
+pub fn create_map(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state: HashState,
+                  options: &Options) -> Box<MacResult+'static> {
+    let disps = state.disps.iter().map(|&(d1, d2)| {
+        quote_expr!(&*cx, ($d1, $d2))
     }).collect();
+    let disps = cx.expr_vec(sp, disps);
 
-    let buckets_len = (entries.len() + DEFAULT_LAMBDA - 1) / DEFAULT_LAMBDA;
-    let mut buckets = Vec::from_fn(buckets_len, |i| Bucket { idx: i, keys: vec![] });
-
-    for (i, hash) in hashes.iter().enumerate() {
-        buckets[(hash.g % (buckets_len as u32)) as uint].keys.push(i);
-    }
-
-    // Sort descending
-    buckets.sort_by(|a, b| a.keys.len().cmp(&b.keys.len()).reverse());
-
-    let table_len = entries.len();
-    let mut map = Vec::from_elem(table_len, None);
-    let mut disps = Vec::from_elem(buckets_len, (0u32, 0u32));
-
-    // store whether an element from the bucket being placed is
-    // located at a certain position, to allow for efficient overlap
-    // checks. It works by storing the generation in each cell and
-    // each new placement-attempt is a new generation, so you can tell
-    // if this is legitimately full by checking that the generations
-    // are equal. (A u64 is far too large to overflow in a reasonable
-    // time for current hardware.)
-    let mut try_map = Vec::from_elem(table_len, 0u64);
-    let mut generation = 0u64;
-
-    // the actual values corresponding to the markers above, as
-    // (index, key) pairs, for adding to the main map once we've
-    // chosen the right disps.
-    let mut values_to_add = vec![];
-
-    'buckets: for bucket in buckets.iter() {
-        for d1 in range(0, table_len as u32) {
-            'disps: for d2 in range(0, table_len as u32) {
-                values_to_add.clear();
-                generation += 1;
-
-                for &key in bucket.keys.iter() {
-                    let idx = (::shared::displace(hashes[key].f1, hashes[key].f2, d1, d2)
-                                % (table_len as u32)) as uint;
-                    if map[idx].is_some() || try_map[idx] == generation {
-                        continue 'disps;
-                    }
-                    try_map[idx] = generation;
-                    values_to_add.push((idx, key));
-                }
-
-                // We've picked a good set of disps
-                disps[bucket.idx] = (d1, d2);
-                for &(idx, key) in values_to_add.iter() {
-                    map[idx] = Some(key);
-                }
-                continue 'buckets;
-            }
-        }
+    let idxs = state.map.iter().map(|&idx| quote_expr!(&*cx, $idx)).collect();
+    let idxs = cx.expr_vec(sp, idxs);
 
-        // Unable to find displacements for a bucket
-        return None;
-    }
+    let entries = entries.iter().map(|&Entry { ref key, ref value, .. }| {
+        quote_expr!(&*cx, ($key, $value))
+    }).collect();
+    let entries = cx.expr_vec(sp, entries);
 
-    Some(HashState {
-        key: key,
-        disps: disps,
-        map: map.into_iter().map(|i| i.unwrap()).collect(),
-    })
+    let hasher = options.hasher.to_expr(cx);
+    let key = state.key;
+    MacExpr::new(quote_expr!(cx, ::phf::Map {
+        hasher: $hasher,
+        key: $key,
+        disps: &$disps,
+        idxs: &$idxs,
+        entries: &$entries,
+    }))
 }
 
-pub fn create_map(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state: HashState)
-                  -> Box<MacResult+'static> {
+/// Returns the displacement and index tables of `state` as array
+/// expressions.
+fn table_exprs(cx: &ExtCtxt, sp: Span, state: &HashState) -> (P<Expr>, P<Expr>) {
     let disps = state.disps.iter().map(|&(d1, d2)| {
-        quote_expr!(&*cx, ($d1, $d2))
+        quote_expr!(cx, ($d1, $d2))
     }).collect();
-    let disps = cx.expr_vec(sp, disps);
+    let idxs = state.map.iter().map(|&idx| quote_expr!(cx, $idx)).collect();
+    (cx.expr_vec(sp, disps), cx.expr_vec(sp, idxs))
+}
+
+/// Creates a `BiMap` from `entries`, whose keys and values are hashed by
+/// `left` and `right` respectively.
+pub fn create_bimap(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, left: HashState,
+                    right: HashState, options: &Options) -> Box<MacResult+'static> {
+    let (left_disps, left_idxs) = table_exprs(cx, sp, &left);
+    let (right_disps, right_idxs) = table_exprs(cx, sp, &right);
 
-    let entries = state.map.iter().map(|&idx| {
-        let &Entry { ref key, ref value, .. } = &entries[idx];
+    let entries = entries.iter().map(|&Entry { ref key, ref value, .. }| {
         quote_expr!(&*cx, ($key, $value))
     }).collect();
     let entries = cx.expr_vec(sp, entries);
 
+    let hasher = options.hasher.to_expr(cx);
+    let left_key = left.key;
+    let right_key = right.key;
+    MacExpr::new(quote_expr!(cx, ::phf::BiMap {
+        hasher: $hasher,
+        left_key: $left_key,
+        left_disps: &$left_disps,
+        left_idxs: &$left_idxs,
+        right_key: $right_key,
+        right_disps: &$right_disps,
+        right_idxs: &$right_idxs,
+        entries: &$entries,
+    }))
+}
+
+/// Creates a `MultiMap` from the distinct `keys` and the values of each key.
+pub fn create_multimap(cx: &mut ExtCtxt, sp: Span, keys: Vec<Entry>, values: Vec<Vec<P<Expr>>>,
+                       state: HashState, options: &Options) -> Box<MacResult+'static> {
+    let (disps, idxs) = table_exprs(cx, sp, &state);
+
+    let mut start = 0u;
+    let entries = keys.iter().zip(values.iter()).map(|(entry, values)| {
+        let key = &entry.key;
+        let end = start + values.len();
+        let expr = quote_expr!(&*cx, ($key, $start, $end));
+        start = end;
+        expr
+    }).collect();
+    let entries = cx.expr_vec(sp, entries);
+    let values = cx.expr_vec(sp, values.into_iter().flat_map(|v| v.into_iter()).collect());
+
+    let hasher = options.hasher.to_expr(cx);
     let key = state.key;
-    MacExpr::new(quote_expr!(cx, ::phf::Map {
+    MacExpr::new(quote_expr!(cx, ::phf::MultiMap {
+        hasher: $hasher,
         key: $key,
         disps: &$disps,
+        idxs: &$idxs,
+        entries: &$entries,
+        values: &$values,
+    }))
+}
+
+/// Creates a `PrefixMap` from `entries`, whose keys must be strings.
+pub fn create_prefix_map(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state: HashState,
+                         options: &Options) -> Box<MacResult+'static> {
+    let mut lengths = entries.iter().map(|e| {
+        match e.key_contents {
+            Key::Str(ref s) => s.get().len(),
+            _ => panic!("non-string prefix map key"),
+        }
+    }).collect::<Vec<_>>();
+    lengths.sort();
+    lengths.dedup();
+    let lengths = lengths.into_iter().map(|len| quote_expr!(&*cx, $len)).collect();
+    let lengths = cx.expr_vec(sp, lengths);
+
+    let map = create_map(cx, sp, entries, state, options).make_expr().unwrap();
+    MacExpr::new(quote_expr!(cx, ::phf::PrefixMap {
+        map: $map,
+        lengths: &$lengths,
+    }))
+}
+
+/// Creates a `SortedMap` from `entries`, which must be sorted by key.
+pub fn create_sorted_map(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state: HashState,
+                         options: &Options) -> Box<MacResult+'static> {
+    let map = create_ordered_map(cx, sp, entries, state, options).make_expr().unwrap();
+    MacExpr::new(quote_expr!(cx, ::phf::SortedMap { map: $map }))
+}
+
+/// A range of keys, with its inclusive bounds.
+pub struct RangeEntry {
+    pub start: Key,
+    pub end: Key,
+    // The source of the range, for error messages.
+    pub span: Span,
+    pub value: P<Expr>,
+}
+
+/// Creates a `RangeMap` from `entries`, which must be sorted and not overlap.
+pub fn create_range_map(cx: &mut ExtCtxt, sp: Span, entries: Vec<RangeEntry>)
+                        -> Box<MacResult+'static> {
+    let entries = entries.iter().map(|entry| {
+        let start = entry.start.to_int_expr(cx, entry.span).unwrap();
+        let end = entry.end.to_int_expr(cx, entry.span).unwrap();
+        let value = &entry.value;
+        quote_expr!(&*cx, ($start, $end, $value))
+    }).collect();
+    let entries = cx.expr_vec(sp, entries);
+
+    MacExpr::new(quote_expr!(cx, ::phf::RangeMap {
         entries: &$entries,
     }))
 }
 
-pub fn create_set(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state: HashState)
-              -> Box<MacResult+'static> {
-    let map = create_map(cx, sp, entries, state).make_expr().unwrap();
+pub fn create_set(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state: HashState,
+                  options: &Options) -> Box<MacResult+'static> {
+    let map = create_map(cx, sp, entries, state, options).make_expr().unwrap();
     MacExpr::new(quote_expr!(cx, ::phf::Set { map: $map }))
 }
 
-pub fn create_ordered_map(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state: HashState)
-                          -> Box<MacResult+'static> {
+pub fn create_ordered_map(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state: HashState,
+                          options: &Options) -> Box<MacResult+'static> {
     let disps = state.disps.iter().map(|&(d1, d2)| {
         quote_expr!(&*cx, ($d1, $d2))
     }).collect();
@@ -236,8 +470,10 @@ pub fn create_ordered_map(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state
     }).collect();
     let entries = cx.expr_vec(sp, entries);
 
+    let hasher = options.hasher.to_expr(cx);
     let key = state.key;
     MacExpr::new(quote_expr!(cx, ::phf::OrderedMap {
+        hasher: $hasher,
         key: $key,
         disps: &$disps,
         idxs: &$idxs,
@@ -245,8 +481,8 @@ pub fn create_ordered_map(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state
     }))
 }
 
-pub fn create_ordered_set(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state: HashState)
-                          -> Box<MacResult+'static> {
-    let map = create_ordered_map(cx, sp, entries, state).make_expr().unwrap();
+pub fn create_ordered_set(cx: &mut ExtCtxt, sp: Span, entries: Vec<Entry>, state: HashState,
+                          options: &Options) -> Box<MacResult+'static> {
+    let map = create_ordered_map(cx, sp, entries, state, options).make_expr().unwrap();
     MacExpr::new(quote_expr!(cx, ::phf::OrderedSet { map: $map }))
 }
@@ -1,7 +1,44 @@
 extern crate xxhash;
 extern crate core;
+extern crate collections;
+extern crate rand;
+extern crate unicode;
 
+use self::core::prelude::*;
+use self::core::fmt;
+use self::core::hash::{Hash, Writer};
+use self::core::iter::order;
 use self::core::kinds::Sized;
+use self::core::num::Int;
+use self::core::str;
+use self::collections::string::String;
+use self::collections::vec::Vec;
+use self::collections::slice::SliceAllocPrelude;
+use self::rand::{Rng, SeedableRng, XorShiftRng};
+
+/// The number of hash keys `generate_hash_keys` will try by default before
+/// giving up.
+pub const DEFAULT_MAX_ATTEMPTS: uint = 1000;
+
+/// Parameters controlling the generation and layout of a hash table.
+#[deriving(Clone)]
+pub struct HashParams {
+    /// The average number of keys in each displacement bucket.
+    pub lambda: uint,
+    /// The ratio of keys to slots in the table, in the range `(0, 1]`.
+    pub load_factor: f64,
+    /// The number of hash keys to try before giving up.
+    pub max_attempts: uint,
+}
+
+/// The parameters used when none are specified.
+pub const DEFAULT_PARAMS: HashParams = HashParams {
+    lambda: 5,
+    load_factor: 1.0,
+    max_attempts: DEFAULT_MAX_ATTEMPTS,
+};
+
+const FIXED_SEED: [u32, ..4] = [3141592653, 589793238, 462643383, 2795028841];
 
 #[inline]
 pub fn displace(f1: u32, f2: u32, d1: u32, d2: u32) -> u32 {
@@ -19,58 +56,834 @@ fn split(hash: u64) -> (u32, u32, u32) {
 }
 
 /// A trait implemented by types which can be used in PHF data structures
+///
+/// Implementations feed the value into a hash state, which is seeded and
+/// finished by the `PhfHasher` of the data structure. Values which are not
+/// equal must feed different input, even when they are combined into tuples
+/// or arrays, so variable length data should be terminated or prefixed with
+/// its length.
 pub trait PhfHash for Sized? {
-    /// Hashes the value of `self`, factoring in a seed
-    fn phf_hash(&self, seed: u64) -> (u32, u32, u32);
+    /// Feeds the value of `self` into `state`.
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer;
+}
+
+/// A trait for types which can be borrowed as another type to look up keys.
+///
+/// Lookups through a borrowed form hash the borrowed value, so
+/// implementations must guarantee that `x.borrow()` hashes and compares
+/// exactly like `x` does.
+pub trait PhfBorrow<Sized? B> for Sized? {
+    /// Borrows `self` as a `B`.
+    fn borrow(&self) -> &B;
+}
+
+impl<T> PhfBorrow<T> for T {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<'a, Sized? T> PhfBorrow<T> for &'a T {
+    #[inline]
+    fn borrow(&self) -> &T {
+        &**self
+    }
+}
+
+impl PhfBorrow<str> for String {
+    #[inline]
+    fn borrow(&self) -> &str {
+        self[]
+    }
+}
+
+impl PhfBorrow<[u8]> for Vec<u8> {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self[]
+    }
+}
+
+/// A family of seeded hash functions used to build and search PHF data
+/// structures.
+///
+/// A table must be searched with the same hasher that was used to build it.
+pub trait PhfHasher {
+    /// Hashes `value`, factoring in a seed.
+    fn hash<Sized? T>(&self, value: &T, seed: u64) -> u64 where T: PhfHash;
+}
+
+/// Hashes `value` with `hasher`, splitting the result into the three parts
+/// used by the CHD algorithm.
+#[inline]
+pub fn phf_hash<Sized? T, H>(hasher: &H, value: &T, seed: u64) -> (u32, u32, u32)
+        where T: PhfHash, H: PhfHasher {
+    split(hasher.hash(value, seed))
 }
 
 impl<'a> PhfHash for &'a str {
     #[inline]
-    fn phf_hash(&self, seed: u64) -> (u32, u32, u32) {
-        split(xxhash::hash_with_seed(seed, self))
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        (**self).phf_hash_into(state)
     }
 }
 
 impl<'a> PhfHash for &'a [u8] {
     #[inline]
-    fn phf_hash(&self, seed: u64) -> (u32, u32, u32) {
-        split(xxhash::oneshot(*self, seed))
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        (**self).phf_hash_into(state)
+    }
+}
+
+impl PhfHash for String {
+    #[inline]
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        self[].phf_hash_into(state)
+    }
+}
+
+impl PhfHash for Vec<u8> {
+    #[inline]
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        self[].phf_hash_into(state)
     }
 }
 
 impl PhfHash for str {
     #[inline]
-    fn phf_hash(&self, seed: u64) -> (u32, u32, u32) {
-        split(xxhash::hash_with_seed(seed, &self))
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        self.hash(state)
     }
 }
 
 impl PhfHash for [u8] {
     #[inline]
-    fn phf_hash(&self, seed: u64) -> (u32, u32, u32) {
-        split(xxhash::oneshot(self, seed))
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        // Unlike a `str`, a byte string can contain any byte, so it can't be
+        // terminated the way `str` is. The length prefix keeps compound keys
+        // like `(b"ab", b"c")` and `(b"a", b"bc")` from hashing identically.
+        (self.len() as u64).hash(state);
+        state.write(self)
     }
 }
 
 
-macro_rules! sip_impl(
+macro_rules! hash_impl(
     ($t:ty) => (
         impl PhfHash for $t {
             #[inline]
-            fn phf_hash(&self, seed: u64) -> (u32, u32, u32) {
-                split(xxhash::hash_with_seed(seed, self))
+            fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+                self.hash(state)
             }
         }
     )
 )
 
-sip_impl!(u8)
-sip_impl!(i8)
-sip_impl!(u16)
-sip_impl!(i16)
-sip_impl!(u32)
-sip_impl!(i32)
-sip_impl!(u64)
-sip_impl!(i64)
-sip_impl!(char)
-sip_impl!(bool)
+hash_impl!(u8)
+hash_impl!(i8)
+hash_impl!(u16)
+hash_impl!(i16)
+hash_impl!(u32)
+hash_impl!(i32)
+hash_impl!(u64)
+hash_impl!(i64)
+hash_impl!(char)
+hash_impl!(bool)
+
+// Pointer-sized integers are hashed as 64 bit values so that tables built on
+// the host match lookups on targets with a different pointer width.
+impl PhfHash for uint {
+    #[inline]
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        (*self as u64).hash(state)
+    }
+}
+
+impl PhfHash for int {
+    #[inline]
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        (*self as i64).hash(state)
+    }
+}
+
+macro_rules! tuple_impl(
+    ($($name:ident)+) => (
+        impl<$($name),+> PhfHash for ($($name,)+) where $($name: PhfHash),+ {
+            #[allow(non_snake_case)]
+            #[inline]
+            fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+                let ($(ref $name,)+) = *self;
+                $($name.phf_hash_into(state);)+
+            }
+        }
+    )
+)
+
+tuple_impl!(A)
+tuple_impl!(A B)
+tuple_impl!(A B C)
+tuple_impl!(A B C D)
+tuple_impl!(A B C D E)
+tuple_impl!(A B C D E F)
+tuple_impl!(A B C D E F G)
+tuple_impl!(A B C D E F G H)
+tuple_impl!(A B C D E F G H I)
+tuple_impl!(A B C D E F G H I J)
+tuple_impl!(A B C D E F G H I J K)
+tuple_impl!(A B C D E F G H I J K L)
+
+// The length of an array is part of its type, so the elements are hashed
+// back to back without a length prefix, the same way tuples are.
+macro_rules! array_impl(
+    ($($n:expr)+) => ($(
+        impl<T> PhfHash for [T, ..$n] where T: PhfHash {
+            #[inline]
+            fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+                for elem in self.iter() {
+                    elem.phf_hash_into(state);
+                }
+            }
+        }
+    )+)
+)
+
+array_impl!(0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30
+            31 32)
+
+/// A string key which is compared and hashed ignoring ASCII case.
+///
+/// `NoCase` keys are written as `NoCase("...")` in a `phf_map!` or `phf_set!`
+/// invocation, and a duplicate key error is raised for keys which differ
+/// only in case. Lookups are made by wrapping the searched string:
+///
+/// ```rust
+/// # #![feature(phase)]
+/// extern crate phf;
+/// #[phase(plugin)]
+/// extern crate phf_mac;
+///
+/// use phf::NoCase;
+///
+/// static HEADERS: phf::Map<NoCase<&'static str>, uint> = phf_map! {
+///     NoCase("Content-Type") => 0,
+///     NoCase("Content-Length") => 1,
+/// };
+///
+/// # fn main() {
+/// let header = String::from_str("content-type");
+/// assert_eq!(Some(&0), HEADERS.get_equiv(&NoCase(header.as_slice())));
+/// # }
+/// ```
+#[deriving(Clone)]
+pub struct NoCase<S>(pub S);
+
+#[doc(hidden)]
+#[inline]
+pub fn ascii_lower(b: u8) -> u8 {
+    if b'A' <= b && b <= b'Z' {
+        b + (b'a' - b'A')
+    } else {
+        b
+    }
+}
+
+impl<S> PhfHash for NoCase<S> where S: Str {
+    #[inline]
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        // Mirrors the hashing of `str`, with each byte folded first.
+        for b in self.0.as_slice().bytes() {
+            state.write(&[ascii_lower(b)]);
+        }
+        state.write(&[0xff]);
+    }
+}
+
+impl<S, T> Equiv<NoCase<T>> for NoCase<S> where S: Str, T: Str {
+    fn equiv(&self, other: &NoCase<T>) -> bool {
+        let a = self.0.as_slice().as_bytes();
+        let b = other.0.as_slice().as_bytes();
+        a.len() == b.len() &&
+            a.iter().zip(b.iter()).all(|(&x, &y)| ascii_lower(x) == ascii_lower(y))
+    }
+}
+
+impl<S> PartialEq for NoCase<S> where S: Str {
+    fn eq(&self, other: &NoCase<S>) -> bool {
+        self.equiv(other)
+    }
+}
+
+impl<S> Eq for NoCase<S> where S: Str {}
+
+impl<S> fmt::Show for NoCase<S> where S: fmt::Show {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+/// A string key which is compared and hashed ignoring Unicode case.
+///
+/// `FoldCase` keys are written as `FoldCase("...")` in a macro invocation,
+/// and are checked for duplicates after folding, just like `NoCase` keys.
+///
+/// Characters which Unicode full case folding expands to several characters
+/// are folded that way, so "straße" matches "STRASSE". Every other character
+/// is folded by mapping it to upper case and then back to lower case, using
+/// the single character case mappings of the Unicode character database,
+/// which approximates Unicode simple case folding but doesn't match it
+/// exactly for a few characters.
+///
+/// ```rust
+/// # #![feature(phase)]
+/// extern crate phf;
+/// #[phase(plugin)]
+/// extern crate phf_mac;
+///
+/// use phf::FoldCase;
+///
+/// static WORDS: phf::Set<FoldCase<&'static str>> = phf_set! {
+///     FoldCase("Σίσυφος"),
+/// };
+///
+/// # fn main() {
+/// assert!(WORDS.contains(&FoldCase("ΣΊΣΥΦΟΣ")));
+/// assert!(WORDS.contains(&FoldCase("σίσυφος")));
+/// assert!(!WORDS.contains(&FoldCase("Sisyphus")));
+/// # }
+/// ```
+#[deriving(Clone)]
+pub struct FoldCase<S>(pub S);
+
+#[inline]
+fn fold_case(c: char) -> char {
+    // Round tripping through upper case also folds characters such as final
+    // sigma which have no upper case form of their own. This is not the
+    // Unicode case folding table, so it differs from it for a few characters.
+    unicode::char::to_lowercase(unicode::char::to_uppercase(c))
+}
+
+// The characters whose full case folding is longer than one character,
+// sorted by character. Taken from the `F` entries of CaseFolding.txt.
+static FULL_FOLDS: &'static [(char, &'static str)] = &[
+    ('\u00df', "ss"),
+    ('\u0130', "i\u0307"),
+    ('\u0149', "\u02bcn"),
+    ('\u01f0', "j\u030c"),
+    ('\u0390', "\u03b9\u0308\u0301"),
+    ('\u03b0', "\u03c5\u0308\u0301"),
+    ('\u0587', "\u0565\u0582"),
+    ('\u1e96', "h\u0331"),
+    ('\u1e97', "t\u0308"),
+    ('\u1e98', "w\u030a"),
+    ('\u1e99', "y\u030a"),
+    ('\u1e9a', "a\u02be"),
+    ('\u1e9e', "ss"),
+    ('\u1f50', "\u03c5\u0313"),
+    ('\u1f52', "\u03c5\u0313\u0300"),
+    ('\u1f54', "\u03c5\u0313\u0301"),
+    ('\u1f56', "\u03c5\u0313\u0342"),
+    ('\u1f80', "\u1f00\u03b9"),
+    ('\u1f81', "\u1f01\u03b9"),
+    ('\u1f82', "\u1f02\u03b9"),
+    ('\u1f83', "\u1f03\u03b9"),
+    ('\u1f84', "\u1f04\u03b9"),
+    ('\u1f85', "\u1f05\u03b9"),
+    ('\u1f86', "\u1f06\u03b9"),
+    ('\u1f87', "\u1f07\u03b9"),
+    ('\u1f88', "\u1f00\u03b9"),
+    ('\u1f89', "\u1f01\u03b9"),
+    ('\u1f8a', "\u1f02\u03b9"),
+    ('\u1f8b', "\u1f03\u03b9"),
+    ('\u1f8c', "\u1f04\u03b9"),
+    ('\u1f8d', "\u1f05\u03b9"),
+    ('\u1f8e', "\u1f06\u03b9"),
+    ('\u1f8f', "\u1f07\u03b9"),
+    ('\u1f90', "\u1f20\u03b9"),
+    ('\u1f91', "\u1f21\u03b9"),
+    ('\u1f92', "\u1f22\u03b9"),
+    ('\u1f93', "\u1f23\u03b9"),
+    ('\u1f94', "\u1f24\u03b9"),
+    ('\u1f95', "\u1f25\u03b9"),
+    ('\u1f96', "\u1f26\u03b9"),
+    ('\u1f97', "\u1f27\u03b9"),
+    ('\u1f98', "\u1f20\u03b9"),
+    ('\u1f99', "\u1f21\u03b9"),
+    ('\u1f9a', "\u1f22\u03b9"),
+    ('\u1f9b', "\u1f23\u03b9"),
+    ('\u1f9c', "\u1f24\u03b9"),
+    ('\u1f9d', "\u1f25\u03b9"),
+    ('\u1f9e', "\u1f26\u03b9"),
+    ('\u1f9f', "\u1f27\u03b9"),
+    ('\u1fa0', "\u1f60\u03b9"),
+    ('\u1fa1', "\u1f61\u03b9"),
+    ('\u1fa2', "\u1f62\u03b9"),
+    ('\u1fa3', "\u1f63\u03b9"),
+    ('\u1fa4', "\u1f64\u03b9"),
+    ('\u1fa5', "\u1f65\u03b9"),
+    ('\u1fa6', "\u1f66\u03b9"),
+    ('\u1fa7', "\u1f67\u03b9"),
+    ('\u1fa8', "\u1f60\u03b9"),
+    ('\u1fa9', "\u1f61\u03b9"),
+    ('\u1faa', "\u1f62\u03b9"),
+    ('\u1fab', "\u1f63\u03b9"),
+    ('\u1fac', "\u1f64\u03b9"),
+    ('\u1fad', "\u1f65\u03b9"),
+    ('\u1fae', "\u1f66\u03b9"),
+    ('\u1faf', "\u1f67\u03b9"),
+    ('\u1fb2', "\u1f70\u03b9"),
+    ('\u1fb3', "\u03b1\u03b9"),
+    ('\u1fb4', "\u03ac\u03b9"),
+    ('\u1fb6', "\u03b1\u0342"),
+    ('\u1fb7', "\u03b1\u0342\u03b9"),
+    ('\u1fbc', "\u03b1\u03b9"),
+    ('\u1fc2', "\u1f74\u03b9"),
+    ('\u1fc3', "\u03b7\u03b9"),
+    ('\u1fc4', "\u03ae\u03b9"),
+    ('\u1fc6', "\u03b7\u0342"),
+    ('\u1fc7', "\u03b7\u0342\u03b9"),
+    ('\u1fcc', "\u03b7\u03b9"),
+    ('\u1fd2', "\u03b9\u0308\u0300"),
+    ('\u1fd3', "\u03b9\u0308\u0301"),
+    ('\u1fd6', "\u03b9\u0342"),
+    ('\u1fd7', "\u03b9\u0308\u0342"),
+    ('\u1fe2', "\u03c5\u0308\u0300"),
+    ('\u1fe3', "\u03c5\u0308\u0301"),
+    ('\u1fe4', "\u03c1\u0313"),
+    ('\u1fe6', "\u03c5\u0342"),
+    ('\u1fe7', "\u03c5\u0308\u0342"),
+    ('\u1ff2', "\u1f7c\u03b9"),
+    ('\u1ff3', "\u03c9\u03b9"),
+    ('\u1ff4', "\u03ce\u03b9"),
+    ('\u1ff6', "\u03c9\u0342"),
+    ('\u1ff7', "\u03c9\u0342\u03b9"),
+    ('\u1ffc', "\u03c9\u03b9"),
+    ('\ufb00', "ff"),
+    ('\ufb01', "fi"),
+    ('\ufb02', "fl"),
+    ('\ufb03', "ffi"),
+    ('\ufb04', "ffl"),
+    ('\ufb05', "st"),
+    ('\ufb06', "st"),
+    ('\ufb13', "\u0574\u0576"),
+    ('\ufb14', "\u0574\u0565"),
+    ('\ufb15', "\u0574\u056b"),
+    ('\ufb16', "\u057e\u0576"),
+    ('\ufb17', "\u0574\u056d"),
+];
+
+fn full_fold(c: char) -> Option<&'static str> {
+    let (mut lo, mut hi) = (0, FULL_FOLDS.len());
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match FULL_FOLDS[mid].0.cmp(&c) {
+            Less => lo = mid + 1,
+            Equal => return Some(FULL_FOLDS[mid].1),
+            Greater => hi = mid,
+        }
+    }
+    None
+}
+
+/// An iterator over the case folded characters of a string.
+#[doc(hidden)]
+pub struct FoldChars<'a> {
+    chars: str::Chars<'a>,
+    // The rest of the expansion of the last character, if it had one.
+    expansion: str::Chars<'static>,
+}
+
+impl<'a> Iterator<char> for FoldChars<'a> {
+    fn next(&mut self) -> Option<char> {
+        match self.expansion.next() {
+            Some(c) => return Some(c),
+            None => {}
+        }
+
+        match self.chars.next() {
+            Some(c) => match full_fold(c) {
+                Some(folded) => {
+                    self.expansion = folded.chars();
+                    self.expansion.next()
+                }
+                None => Some(fold_case(c)),
+            },
+            None => None,
+        }
+    }
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn fold_str<'a>(s: &'a str) -> FoldChars<'a> {
+    FoldChars {
+        chars: s.chars(),
+        expansion: "".chars(),
+    }
+}
+
+impl<S> PhfHash for FoldCase<S> where S: Str {
+    #[inline]
+    fn phf_hash_into<W>(&self, state: &mut W) where W: Writer {
+        for c in fold_str(self.0.as_slice()) {
+            c.hash(state);
+        }
+        state.write(&[0xff]);
+    }
+}
+
+impl<S, T> Equiv<FoldCase<T>> for FoldCase<S> where S: Str, T: Str {
+    fn equiv(&self, other: &FoldCase<T>) -> bool {
+        order::eq(fold_str(self.0.as_slice()), fold_str(other.0.as_slice()))
+    }
+}
+
+impl<S> PartialEq for FoldCase<S> where S: Str {
+    fn eq(&self, other: &FoldCase<S>) -> bool {
+        self.equiv(other)
+    }
+}
+
+impl<S> Eq for FoldCase<S> where S: Str {}
+
+impl<S> fmt::Show for FoldCase<S> where S: fmt::Show {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+/// The default `PhfHasher`, using xxHash.
+#[deriving(Clone)]
+pub struct XxHasher;
+
+impl PhfHasher for XxHasher {
+    #[inline]
+    fn hash<Sized? T>(&self, value: &T, seed: u64) -> u64 where T: PhfHash {
+        let mut state = xxhash::XXState::new_with_seed(seed);
+        value.phf_hash_into(&mut state);
+        state.digest()
+    }
+}
+
+/// A `PhfHasher` using SipHash-1-3 keyed by the seed.
+#[deriving(Clone)]
+pub struct Sip13Hasher;
+
+struct Sip13State {
+    length: uint,
+    v0: u64,
+    v1: u64,
+    v2: u64,
+    v3: u64,
+    tail: u64,
+    ntail: uint,
+}
+
+macro_rules! sip_round(
+    ($v0:expr, $v1:expr, $v2:expr, $v3:expr) => ({
+        $v0 += $v1; $v1 = $v1.rotate_left(13); $v1 ^= $v0; $v0 = $v0.rotate_left(32);
+        $v2 += $v3; $v3 = $v3.rotate_left(16); $v3 ^= $v2;
+        $v0 += $v3; $v3 = $v3.rotate_left(21); $v3 ^= $v0;
+        $v2 += $v1; $v1 = $v1.rotate_left(17); $v1 ^= $v2; $v2 = $v2.rotate_left(32);
+    })
+)
+
+impl Sip13State {
+    fn new(k0: u64, k1: u64) -> Sip13State {
+        Sip13State {
+            length: 0,
+            v0: k0 ^ 0x736f6d6570736575,
+            v1: k1 ^ 0x646f72616e646f6d,
+            v2: k0 ^ 0x6c7967656e657261,
+            v3: k1 ^ 0x7465646279746573,
+            tail: 0,
+            ntail: 0,
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        let mut v0 = self.v0;
+        let mut v1 = self.v1;
+        let mut v2 = self.v2;
+        let mut v3 = self.v3;
+
+        let b = ((self.length as u64 & 0xff) << 56) | self.tail;
+
+        v3 ^= b;
+        sip_round!(v0, v1, v2, v3);
+        v0 ^= b;
+
+        v2 ^= 0xff;
+        sip_round!(v0, v1, v2, v3);
+        sip_round!(v0, v1, v2, v3);
+        sip_round!(v0, v1, v2, v3);
+
+        v0 ^ v1 ^ v2 ^ v3
+    }
+}
+
+impl Writer for Sip13State {
+    fn write(&mut self, bytes: &[u8]) {
+        self.length += bytes.len();
+        for &b in bytes.iter() {
+            self.tail |= (b as u64) << (8 * self.ntail);
+            self.ntail += 1;
+            if self.ntail == 8 {
+                let m = self.tail;
+                self.v3 ^= m;
+                sip_round!(self.v0, self.v1, self.v2, self.v3);
+                self.v0 ^= m;
+                self.tail = 0;
+                self.ntail = 0;
+            }
+        }
+    }
+}
+
+impl PhfHasher for Sip13Hasher {
+    #[inline]
+    fn hash<Sized? T>(&self, value: &T, seed: u64) -> u64 where T: PhfHash {
+        let mut state = Sip13State::new(seed, 0);
+        value.phf_hash_into(&mut state);
+        state.finish()
+    }
+}
+
+/// A `PhfHasher` using the fast, low quality hash function from Firefox.
+#[deriving(Clone)]
+pub struct FxHasher;
+
+struct FxState {
+    hash: u64,
+}
+
+impl Writer for FxState {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes.iter() {
+            self.hash = (self.hash.rotate_left(5) ^ b as u64) * 0x517cc1b727220a95;
+        }
+    }
+}
+
+impl PhfHasher for FxHasher {
+    #[inline]
+    fn hash<Sized? T>(&self, value: &T, seed: u64) -> u64 where T: PhfHash {
+        let mut state = FxState { hash: seed };
+        value.phf_hash_into(&mut state);
+        state.hash
+    }
+}
+
+/// A `PhfHasher` using a multiply-shift hash.
+///
+/// This is intended for integral keys. Keys longer than 8 bytes are folded
+/// together before hashing, so it should not be used for strings.
+#[deriving(Clone)]
+pub struct MulShiftHasher;
+
+struct MulShiftState {
+    value: u64,
+}
+
+impl Writer for MulShiftState {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes.iter() {
+            self.value = self.value.rotate_left(8) ^ b as u64;
+        }
+    }
+}
+
+impl PhfHasher for MulShiftHasher {
+    #[inline]
+    fn hash<Sized? T>(&self, value: &T, seed: u64) -> u64 where T: PhfHash {
+        let mut state = MulShiftState { value: 0 };
+        value.phf_hash_into(&mut state);
+        let hash = (state.value ^ seed) * 0x9e3779b97f4a7c15;
+        hash ^ (hash >> 32)
+    }
+}
+
+/// The output of a successful perfect hash generation.
+pub struct HashState {
+    /// The seed passed to `PhfHasher::hash`.
+    pub key: u64,
+    /// The displacement pair for each bucket.
+    pub disps: Vec<(u32, u32)>,
+    /// The index into the input keys stored in each slot of the table.
+    ///
+    /// If the table is larger than the number of keys, unoccupied slots
+    /// point at the first key. Lookups will never land on those slots for
+    /// that key, so the key comparison will reject them.
+    pub map: Vec<uint>,
+}
+
+/// Returns the number of slots in a table holding `len` keys at the given
+/// load factor.
+pub fn table_len(len: uint, load_factor: f64) -> uint {
+    let table_len = (len as f64 / load_factor) as uint;
+    if table_len < len { len } else { table_len }
+}
+
+/// Generates a perfect hash over `keys`, making at most
+/// `params.max_attempts` attempts.
+///
+/// A fixed seed is used, so a given set of keys will always produce the same
+/// `HashState`, whether the hash is generated inside of a `phf_*` macro or at
+/// runtime.
+pub fn generate_hash_keys<K, H>(keys: &[K], hasher: &H, params: &HashParams)
+                                -> Option<HashState> where K: PhfHash, H: PhfHasher {
+    let mut rng: XorShiftRng = SeedableRng::from_seed(FIXED_SEED);
+    for _ in range(0, params.max_attempts) {
+        match try_generate_hash(keys, hasher, params, &mut rng) {
+            Some(s) => return Some(s),
+            None => {}
+        }
+    }
+    None
+}
+
+// Records the input a key feeds to a hasher.
+struct HashInput {
+    bytes: Vec<u8>,
+}
+
+impl Writer for HashInput {
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.push_all(bytes);
+    }
+}
+
+/// Returns groups of indices of the keys in `keys` which feed identical input
+/// to a `PhfHasher`.
+///
+/// Such keys hash identically under every hasher and seed, so they can never
+/// be placed in the same table.
+pub fn find_collisions<K>(keys: &[K]) -> Vec<Vec<uint>> where K: PhfHash {
+    let mut inputs = keys.iter().enumerate().map(|(i, k)| {
+        let mut input = HashInput { bytes: Vec::new() };
+        k.phf_hash_into(&mut input);
+        (input.bytes, i)
+    }).collect::<Vec<_>>();
+    inputs.sort();
+
+    let mut collisions = Vec::new();
+    let mut start = 0;
+    while start < inputs.len() {
+        let mut end = start + 1;
+        while end < inputs.len() && inputs[end].0 == inputs[start].0 {
+            end += 1;
+        }
+        if end - start > 1 {
+            collisions.push(inputs.slice(start, end).iter().map(|&(_, i)| i).collect());
+        }
+        start = end;
+    }
+
+    collisions
+}
+
+/// Makes a single attempt at finding displacements for `keys` using a hash
+/// key drawn from `rng`.
+pub fn try_generate_hash<K, H, R>(keys: &[K], hasher: &H, params: &HashParams, rng: &mut R)
+                                  -> Option<HashState>
+        where K: PhfHash, H: PhfHasher, R: Rng {
+    struct Bucket {
+        idx: uint,
+        keys: Vec<uint>,
+    }
+
+    struct Hashes {
+        g: u32,
+        f1: u32,
+        f2: u32,
+    }
+
+    let key = rng.gen();
+
+    let hashes: Vec<_> = keys.iter().map(|k| {
+        let (g, f1, f2) = phf_hash(hasher, k, key);
+        Hashes {
+            g: g,
+            f1: f1,
+            f2: f2
+        }
+    }).collect();
+
+    let buckets_len = (keys.len() + params.lambda - 1) / params.lambda;
+    let mut buckets = Vec::from_fn(buckets_len, |i| Bucket { idx: i, keys: Vec::new() });
+
+    for (i, hash) in hashes.iter().enumerate() {
+        buckets[(hash.g % (buckets_len as u32)) as uint].keys.push(i);
+    }
+
+    // Keys in the same bucket with identical hashes can never be displaced
+    // into different slots, so don't bother searching.
+    for bucket in buckets.iter() {
+        for (i, &a) in bucket.keys.iter().enumerate() {
+            for &b in bucket.keys.slice_from(i + 1).iter() {
+                if hashes[a].f1 == hashes[b].f1 && hashes[a].f2 == hashes[b].f2 {
+                    return None;
+                }
+            }
+        }
+    }
+
+    // Sort descending
+    buckets.sort_by(|a, b| a.keys.len().cmp(&b.keys.len()).reverse());
+
+    let table_len = table_len(keys.len(), params.load_factor);
+    let mut map = Vec::from_elem(table_len, None);
+    let mut disps = Vec::from_elem(buckets_len, (0u32, 0u32));
+
+    // store whether an element from the bucket being placed is
+    // located at a certain position, to allow for efficient overlap
+    // checks. It works by storing the generation in each cell and
+    // each new placement-attempt is a new generation, so you can tell
+    // if this is legitimately full by checking that the generations
+    // are equal. (A u64 is far too large to overflow in a reasonable
+    // time for current hardware.)
+    let mut try_map = Vec::from_elem(table_len, 0u64);
+    let mut generation = 0u64;
+
+    // the actual values corresponding to the markers above, as
+    // (index, key) pairs, for adding to the main map once we've
+    // chosen the right disps.
+    let mut values_to_add = Vec::new();
+
+    'buckets: for bucket in buckets.iter() {
+        for d1 in range(0, table_len as u32) {
+            'disps: for d2 in range(0, table_len as u32) {
+                values_to_add.clear();
+                generation += 1;
+
+                for &key in bucket.keys.iter() {
+                    let idx = (displace(hashes[key].f1, hashes[key].f2, d1, d2)
+                                % (table_len as u32)) as uint;
+                    if map[idx].is_some() || try_map[idx] == generation {
+                        continue 'disps;
+                    }
+                    try_map[idx] = generation;
+                    values_to_add.push((idx, key));
+                }
+
+                // We've picked a good set of disps
+                disps[bucket.idx] = (d1, d2);
+                for &(idx, key) in values_to_add.iter() {
+                    map[idx] = Some(key);
+                }
+                continue 'buckets;
+            }
+        }
+
+        // Unable to find displacements for a bucket
+        return None;
+    }
+
+    Some(HashState {
+        key: key,
+        disps: disps,
+        map: map.into_iter().map(|i| i.unwrap_or(0)).collect(),
+    })
+}